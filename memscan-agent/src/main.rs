@@ -0,0 +1,83 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    net::TcpListener,
+    os::unix::{fs::FileTypeExt, net::UnixListener},
+    sync::Arc,
+    thread,
+};
+
+use memscan::{
+    memory_source::{MemorySource, ProcMemSource},
+    remote::{self, DEFAULT_PORT},
+};
+
+/// Environment variable with the token clients have to send.
+const TOKEN_VARIABLE: &str = "MEMSCAN_AGENT_TOKEN";
+
+fn open(pid: i32) -> io::Result<Box<dyn MemorySource>> {
+    /* Processes we may not write to can still be searched */
+    let source = ProcMemSource::open(pid, true).or_else(|_| ProcMemSource::open(pid, false))?;
+    Ok(Box::new(source))
+}
+
+/// The token from the environment, or a random one that is printed.
+fn token() -> io::Result<String> {
+    if let Ok(token) = std::env::var(TOKEN_VARIABLE)
+        && !token.is_empty()
+    {
+        return Ok(token);
+    }
+    let mut random = [0u8; 16];
+    File::open("/dev/urandom")?.read_exact(&mut random)?;
+    let token: String = random.iter().map(|byte| format!("{:02x}", byte)).collect();
+    println!("Token: {}", token);
+    Ok(token)
+}
+
+fn handle(reader: impl io::Read, writer: impl io::Write, token: &str, peer: String) {
+    println!("Client {} connected", peer);
+    match remote::serve(reader, writer, token, open) {
+        Ok(()) => println!("Client {} disconnected", peer),
+        Err(error) => println!("[ERROR]: Client {}: {}", peer, error),
+    }
+}
+
+/// Serves the processes of this machine to anyone who knows the token. With
+/// it a client can read and write the memory of every process the agent may
+/// trace, so keep the agent on localhost or a Unix socket and tunnel it over
+/// ssh rather than exposing the port.
+fn main() -> io::Result<()> {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or(format!("127.0.0.1:{}", DEFAULT_PORT));
+    let token = Arc::new(token()?);
+
+    if let Some(path) = address.strip_prefix("unix:") {
+        /* A socket left behind by an earlier run would make bind fail, other
+        files at the path are left alone */
+        if fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+            fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        println!("Listening on {}", address);
+        for (index, stream) in listener.incoming().enumerate() {
+            let stream = stream?;
+            let reader = stream.try_clone()?;
+            let token = token.clone();
+            thread::spawn(move || handle(reader, stream, &token, format!("#{}", index)));
+        }
+    } else {
+        let listener = TcpListener::bind(&address)?;
+        println!("Listening on {}", address);
+        for stream in listener.incoming() {
+            let stream = stream?;
+            stream.set_nodelay(true)?;
+            let peer = stream.peer_addr()?.to_string();
+            let reader = stream.try_clone()?;
+            let token = token.clone();
+            thread::spawn(move || handle(reader, stream, &token, peer));
+        }
+    }
+    Ok(())
+}
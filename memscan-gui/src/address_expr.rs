@@ -0,0 +1,261 @@
+use std::{fmt::Display, path::Path};
+
+use crate::{
+    app_error::AppError,
+    data_types::{DataType, WrappedValue},
+    module_address::map_path,
+    process_memory,
+    search::read_value,
+};
+
+/// An address given as an expression such as `[[libgame.so+0x1A2B0]+0x18]+0x40`.
+/// Brackets dereference a pointer, module names stand for the lowest address
+/// the module is mapped at and numbers are hexadecimal.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AddressExpr {
+    Address(u64),
+    Module(String),
+    Deref(Box<AddressExpr>),
+    Offset(Box<AddressExpr>, i64),
+}
+
+impl Display for AddressExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AddressExpr::Address(address) => write!(f, "{:#X}", address),
+            AddressExpr::Module(module) => write!(f, "{}", module),
+            AddressExpr::Deref(inner) => write!(f, "[{}]", inner),
+            AddressExpr::Offset(inner, offset) if *offset < 0 => {
+                write!(f, "{}-{:#X}", inner, offset.unsigned_abs())
+            }
+            AddressExpr::Offset(inner, offset) => write!(f, "{}+{:#X}", inner, offset),
+        }
+    }
+}
+
+fn parse_error(input: &str, message: &str) -> AppError {
+    AppError::AddressParseError(format!("{}: {}", input, message))
+}
+
+fn parse_hex(token: &str) -> Option<u64> {
+    let digits = token
+        .strip_prefix("0x")
+        .or_else(|| token.strip_prefix("0X"))
+        .unwrap_or(token);
+    u64::from_str_radix(digits, 16).ok()
+}
+
+/// Whether `text` starts with a hex number that ends at an operator or at
+/// the end of the input.
+fn offset_follows(text: &str) -> bool {
+    let end = text.find(['[', ']', '+', '-']).unwrap_or(text.len());
+    parse_hex(text[..end].trim()).is_some()
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    rest: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&mut self) -> Option<char> {
+        self.rest = self.rest.trim_start();
+        self.rest.chars().next()
+    }
+
+    fn bump(&mut self) {
+        self.rest = &self.rest[1..];
+    }
+
+    /// Anything up to the next operator. `+` and `-` are only operators when
+    /// a hex offset follows, so module names like `ld-linux.so` or
+    /// `libstdc++.so.6` stay intact.
+    fn token(&mut self) -> &'a str {
+        self.rest = self.rest.trim_start();
+        let rest = self.rest;
+        let end = rest
+            .char_indices()
+            .find(|&(index, c)| match c {
+                '[' | ']' => true,
+                '+' | '-' => offset_follows(&rest[index + 1..]),
+                _ => false,
+            })
+            .map_or(rest.len(), |(index, _)| index);
+        let (token, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        token.trim_end()
+    }
+
+    fn expression(&mut self) -> Result<AddressExpr, AppError> {
+        let mut expr = match self.peek() {
+            Some('[') => {
+                self.bump();
+                let inner = self.expression()?;
+                if self.peek() != Some(']') {
+                    return Err(parse_error(self.input, "missing `]`"));
+                }
+                self.bump();
+                AddressExpr::Deref(Box::new(inner))
+            }
+            Some(_) => {
+                let token = self.token();
+                if token.is_empty() {
+                    return Err(parse_error(self.input, "expected an address"));
+                }
+                match parse_hex(token) {
+                    Some(address) => AddressExpr::Address(address),
+                    None => AddressExpr::Module(token.to_string()),
+                }
+            }
+            None => return Err(parse_error(self.input, "expected an address")),
+        };
+
+        while let Some(sign @ ('+' | '-')) = self.peek() {
+            self.bump();
+            let token = self.token();
+            let offset = parse_hex(token)
+                .and_then(|offset| i64::try_from(offset).ok())
+                .ok_or_else(|| parse_error(self.input, &format!("invalid offset `{}`", token)))?;
+            let offset = if sign == '-' { -offset } else { offset };
+            expr = AddressExpr::Offset(Box::new(expr), offset);
+        }
+        Ok(expr)
+    }
+}
+
+/// Lowest address `module` is mapped at, matching either the file name or
+/// the full path.
+fn module_base(pid: i32, module: &str) -> Result<Option<u64>, AppError> {
+    let maps = process_memory::regions(pid)?;
+    Ok(maps
+        .iter()
+        .filter(|map| {
+            map_path(map).is_some_and(|path| {
+                path == module
+                    || Path::new(&path)
+                        .file_name()
+                        .is_some_and(|name| name == module)
+            })
+        })
+        .map(|map| map.start)
+        .min())
+}
+
+impl AddressExpr {
+    pub fn parse(input: &str) -> Result<Self, AppError> {
+        let mut parser = Parser { input, rest: input };
+        let expr = parser.expression()?;
+        if parser.peek().is_some() {
+            return Err(parse_error(input, &format!("unexpected `{}`", parser.rest)));
+        }
+        Ok(expr)
+    }
+
+    /// Evaluates the expression in `pid`. Dereferences are numbered from 1 in
+    /// the order they happen, a failing one is reported with the address it
+    /// tried to read.
+    pub fn resolve(&self, pid: i32) -> Result<u64, AppError> {
+        let mut step = 0;
+        self.resolve_step(pid, &mut step)
+    }
+
+    fn resolve_step(&self, pid: i32, step: &mut usize) -> Result<u64, AppError> {
+        match self {
+            AddressExpr::Address(address) => Ok(*address),
+            AddressExpr::Module(module) => module_base(pid, module)?
+                .ok_or_else(|| AppError::Io(format!("{} is not mapped", module))),
+            AddressExpr::Deref(inner) => {
+                let address = inner.resolve_step(pid, step)?;
+                *step += 1;
+                match read_value(pid, address, DataType::U64) {
+                    Ok(WrappedValue::U64(pointer)) => Ok(pointer),
+                    _ => Err(AppError::InvalidPointer {
+                        step: *step,
+                        address,
+                    }),
+                }
+            }
+            AddressExpr::Offset(inner, offset) => {
+                Ok(inner.resolve_step(pid, step)?.wrapping_add_signed(*offset))
+            }
+        }
+    }
+}
+
+/// Where a tracked value lives, either a fixed address or an expression that
+/// is evaluated again on every refresh.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Location {
+    Address(u64),
+    Expression(AddressExpr),
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Location::Address(address) => write!(f, "{:#x}", address),
+            Location::Expression(expr) => write!(f, "{}", expr),
+        }
+    }
+}
+
+impl Location {
+    pub fn resolve(&self, pid: i32) -> Result<u64, AppError> {
+        match self {
+            Location::Address(address) => Ok(*address),
+            Location::Expression(expr) => expr.resolve(pid),
+        }
+    }
+}
+
+#[test]
+fn test_address_expr_parse() {
+    let module = |name: &str| AddressExpr::Module(name.to_string());
+    let offset = |expr, offset| AddressExpr::Offset(Box::new(expr), offset);
+    let deref = |expr| AddressExpr::Deref(Box::new(expr));
+    let parse = |input| AddressExpr::parse(input).unwrap_or_else(|err| panic!("{}", err));
+
+    assert_eq!(parse("0x1000"), AddressExpr::Address(0x1000));
+    assert_eq!(parse("ld-linux.so"), module("ld-linux.so"));
+    assert_eq!(parse("module-0x10"), offset(module("module"), -0x10));
+    assert_eq!(
+        parse("0x1000 - 8"),
+        offset(AddressExpr::Address(0x1000), -8)
+    );
+    assert_eq!(
+        parse("[libstdc++.so.6+0x10]+0x8"),
+        offset(deref(offset(module("libstdc++.so.6"), 0x10)), 8)
+    );
+    assert_eq!(
+        parse("[[libgame.so+0x1A2B0]+0x18]+0x40"),
+        offset(
+            deref(offset(deref(offset(module("libgame.so"), 0x1A2B0)), 0x18)),
+            0x40
+        )
+    );
+    for input in [
+        "",
+        "[0x10",
+        "0x10]",
+        "libgame.so+0x10]",
+        "[]",
+        "0x10+0x8+zz",
+    ] {
+        assert!(AddressExpr::parse(input).is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn test_address_expr_display() {
+    for input in [
+        "0x1000",
+        "ld-linux.so-0x10",
+        "[libstdc++.so.6+0x10]+0x8",
+        "[[libgame.so+0x1A2B0]+0x18]-0x40",
+    ] {
+        let expr = AddressExpr::parse(input).unwrap_or_else(|err| panic!("{}", err));
+        assert_eq!(expr.to_string(), input);
+        let again = AddressExpr::parse(&expr.to_string()).unwrap_or_else(|err| panic!("{}", err));
+        assert_eq!(again, expr);
+    }
+}
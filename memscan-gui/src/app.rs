@@ -1,16 +1,38 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+};
 
 use egui::{Align, Layout, ScrollArea, Sense, Window};
 use egui_extras::{Column, TableBuilder};
+use memscan::{
+    export::{self, ExportRecord},
+    search::relative::Relation,
+};
 
 use crate::{
+    address_expr::{AddressExpr, Location},
     app_error::AppError,
-    data_types::{DataType, WrappedValue},
+    change_scan::ChangeScan,
+    cheat_table::{CheatEntry, CheatTable, TableLocation},
+    data_types::{DataType, WrappedValue, relation_picker},
     endian::Endianness,
+    module_address::ModuleAddress,
+    pointer_scan::PointerScanner,
+    process_memory,
     process_picker::ProcessPicker,
-    search::{self, SearchRegion, read_value},
+    process_stop::StoppedProcesses,
+    region_filter::{RegionFilter, RegionPreset},
+    scan_history::ScanHistory,
+    search::{
+        self, GroupComponent, ScanCoverage, SearchRegion, SearchResult, ValueHistory, read_value,
+        write_value,
+    },
     search_scope::SearchScope,
+    session::{ScanStep, Session},
     settings::Settings,
+    table_import,
 };
 
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -36,15 +58,61 @@ pub struct TemplateApp {
 
     #[serde(skip)]
     search_scope: SearchScope,
+    /// Regions searched with `SearchScope::Custom`
+    region_filter: RegionFilter,
+    #[serde(skip)]
+    preset_name: String,
 
     #[serde(skip)]
     endianness: Endianness,
 
     #[serde(skip)]
-    search_results: Vec<(i32, Vec<u64>)>,
+    group_components: Vec<GroupComponent>,
+
+    #[serde(skip)]
+    group_distance: u64,
+
+    #[serde(skip)]
+    group_ordered: bool,
+
+    #[serde(skip)]
+    relation: Relation,
+
+    #[serde(skip)]
+    relative_values: String,
+
+    #[serde(skip)]
+    value_history: ValueHistory,
+
+    #[serde(skip)]
+    search_results: Vec<SearchResult>,
+
+    /// Memory read by the last full scan
+    #[serde(skip)]
+    coverage: Option<ScanCoverage>,
+
+    /// Memory to compare against for changed and unchanged scans
+    #[serde(skip)]
+    change_scan: Option<ChangeScan>,
+
+    #[serde(skip)]
+    scan_history: ScanHistory,
 
     #[serde(skip)]
-    tracked_addresses: HashMap<(i32, u64), WrappedValue>,
+    tracked_addresses: HashMap<(i32, Location), TrackedAddress>,
+
+    #[serde(skip)]
+    tracked_expression: String,
+
+    /// Entries not found in any selected process yet
+    #[serde(skip)]
+    pending_table: CheatTable,
+
+    #[serde(skip)]
+    file_dialog: Option<FileDialog>,
+
+    #[serde(skip)]
+    pointer_scanner: PointerScanner,
 
     #[serde(skip)]
     error: Option<AppError>,
@@ -52,11 +120,122 @@ pub struct TemplateApp {
     settings: Settings,
 }
 
+const CHEAT_TABLE_KEY: &str = "cheat_table";
+
+struct TrackedAddress {
+    description: String,
+    value: WrappedValue,
+    module: Option<ModuleAddress>,
+    /// Address the location resolved to on the last refresh
+    address: u64,
+    /// Frozen values are written back on every refresh
+    frozen: bool,
+    /// Why the last refresh failed, the previous value is kept meanwhile
+    error: Option<String>,
+}
+
+impl TrackedAddress {
+    fn new(value: WrappedValue, module: Option<ModuleAddress>, address: u64) -> Self {
+        Self {
+            description: String::new(),
+            value,
+            module,
+            address,
+            frozen: false,
+            error: None,
+        }
+    }
+
+    /// Finds the entry in `pid`, frozen entries take their stored value.
+    fn from_entry(pid: i32, entry: &CheatEntry) -> Result<((i32, Location), Self), AppError> {
+        let (location, module) = match &entry.location {
+            TableLocation::Address(address) => (Location::Address(*address), None),
+            TableLocation::Module(module) => {
+                let address = module
+                    .resolve_in(pid)?
+                    .ok_or_else(|| AppError::Io(format!("{} is not mapped", module)))?;
+                (Location::Address(address), Some(module.clone()))
+            }
+            TableLocation::Expression(expr) => {
+                (Location::Expression(AddressExpr::parse(expr)?), None)
+            }
+        };
+        let address = location.resolve(pid)?;
+        let value = match (&entry.value, entry.frozen) {
+            (Some(value), true) => entry.data_type.parse(value)?,
+            _ => read_value(pid, address, entry.data_type)?,
+        };
+
+        let mut tracked = Self::new(value, module, address);
+        tracked.description = entry.description.clone();
+        tracked.frozen = entry.frozen;
+        Ok(((pid, location), tracked))
+    }
+
+    fn to_entry(&self, location: &Location) -> CheatEntry {
+        let location = match (location, &self.module) {
+            (Location::Address(_), Some(module)) => TableLocation::Module(module.clone()),
+            (Location::Address(address), None) => TableLocation::Address(*address),
+            (Location::Expression(expr), _) => TableLocation::Expression(expr.to_string()),
+        };
+        CheatEntry {
+            description: self.description.clone(),
+            data_type: self.value.data_type(),
+            location,
+            frozen: self.frozen,
+            value: self.frozen.then(|| self.value.to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum FileAction {
+    Open,
+    Save,
+    /// Read a table of another tool
+    Import,
+    ExportResults,
+    ExportTracked,
+    OpenSession,
+    SaveSession,
+    /// Add an ELF core file to the processes
+    OpenCoreDump,
+    /// Write the in-scope regions of the selected process to a snapshot
+    DumpProcess,
+    /// Add a snapshot to the processes
+    OpenSnapshot,
+    /// Add the processes of a `memscan-agent`, the path is its token and
+    /// address
+    ConnectAgent,
+    /// Add the target of a gdb stub, the path is its address
+    ConnectGdb,
+}
+
+impl FileAction {
+    fn default_path(self) -> &'static str {
+        match self {
+            FileAction::Open | FileAction::Save | FileAction::Import => "table.json",
+            FileAction::ExportResults => "results.csv",
+            FileAction::ExportTracked => "tracked.csv",
+            FileAction::OpenSession | FileAction::SaveSession => "session.mss",
+            FileAction::OpenCoreDump => "core",
+            FileAction::DumpProcess | FileAction::OpenSnapshot => "process.msss",
+            FileAction::ConnectAgent => "token@127.0.0.1:7331",
+            FileAction::ConnectGdb => "127.0.0.1:1234",
+        }
+    }
+}
+
+/// Path for one of the file actions of the File menu.
+struct FileDialog {
+    path: String,
+    action: FileAction,
+}
+
 struct Editor {
     pid: i32,
-    pointer: u64,
+    location: Location,
     data_type: DataType,
-    endianness: Endianness,
 }
 
 impl Default for TemplateApp {
@@ -69,9 +248,24 @@ impl Default for TemplateApp {
             search_regions: vec![],
             data_type: DataType::default(),
             search_scope: SearchScope::default(),
+            region_filter: RegionFilter::default(),
+            preset_name: String::new(),
             endianness: Endianness::default(),
+            group_components: vec![],
+            group_distance: 64,
+            group_ordered: false,
+            relation: Relation::default(),
+            relative_values: String::new(),
+            value_history: ValueHistory::new(),
             search_results: vec![],
+            coverage: None,
+            change_scan: None,
+            scan_history: ScanHistory::default(),
             tracked_addresses: HashMap::new(),
+            tracked_expression: String::new(),
+            pending_table: CheatTable::default(),
+            file_dialog: None,
+            pointer_scanner: PointerScanner::default(),
             error: None,
             settings: Settings::default(),
         }
@@ -86,17 +280,280 @@ impl TemplateApp {
             _self.search_scope = _self.settings.default_search_scope;
             _self.data_type = _self.settings.default_data_type;
             _self.endianness = _self.settings.default_endianness;
+            _self.pending_table = eframe::get_value(storage, CHEAT_TABLE_KEY).unwrap_or_default();
             return _self;
         }
 
         Default::default()
     }
+
+    /// The tracked and pending entries, associated with the process of the
+    /// pending entries or else the first selected one.
+    fn cheat_table(&self) -> CheatTable {
+        let process = match self.pending_table.process.is_empty() {
+            false => self.pending_table.process.clone(),
+            true => self
+                .process_picker
+                .selected_processes()
+                .first()
+                .map(|process| process.cmd.clone())
+                .unwrap_or_default(),
+        };
+        let tracked = self
+            .tracked_addresses
+            .iter()
+            .map(|((_, location), tracked)| tracked.to_entry(location));
+        let entries = self
+            .pending_table
+            .entries
+            .iter()
+            .cloned()
+            .chain(tracked)
+            .collect();
+        CheatTable::new(process, entries)
+    }
+
+    /// Looks for the pending entries in the selected processes.
+    fn resolve_pending(&mut self) {
+        let selected: Vec<_> = self
+            .process_picker
+            .selected_processes()
+            .into_iter()
+            .filter(|process| self.pending_table.applies_to(&process.cmd))
+            .collect();
+        let mut pending = vec![];
+        for entry in std::mem::take(&mut self.pending_table.entries) {
+            let resolved = selected
+                .iter()
+                .find_map(|process| TrackedAddress::from_entry(process.pid, &entry).ok());
+            match resolved {
+                Some((key, tracked)) => {
+                    self.tracked_addresses.insert(key, tracked);
+                }
+                None => pending.push(entry),
+            }
+        }
+        self.pending_table.entries = pending;
+    }
+
+    fn open_table(&mut self, path: &str) -> Result<(), AppError> {
+        self.pending_table = CheatTable::load(path)?;
+        self.resolve_pending();
+        Ok(())
+    }
+
+    /// Restores the results and history of a session, rebasing them onto
+    /// the running processes.
+    fn open_session(&mut self, path: &str) -> Result<(), AppError> {
+        let session = Session::load(path)?;
+        let steps = session.history.clone();
+        let (results, dropped) = session.resume(self.process_picker.processes());
+        if dropped > 0 {
+            println!(
+                "[WARN]: Dropped {} results that could not be rebased",
+                dropped
+            );
+        }
+        self.scan_history = ScanHistory::restore(steps, &results);
+        self.search_results = results;
+        self.value_history.clear();
+        Ok(())
+    }
+
+    /// Runs `scan` with the scanned processes stopped if enabled in the
+    /// settings, they are resumed when it returns.
+    fn while_stopped<T>(
+        &mut self,
+        scan: impl FnOnce(&mut Self) -> Result<T, AppError>,
+    ) -> Result<T, AppError> {
+        let _stopped = if self.settings.stop_processes {
+            let regions = self.search_regions.iter().map(|region| region.pid);
+            let results = self.search_results.iter().map(|result| result.pid);
+            Some(StoppedProcesses::stop(regions.chain(results))?)
+        } else {
+            None
+        };
+        scan(self)
+    }
+
+    /// Remembers a successful scan, `restart` begins a new history.
+    fn record_step(&mut self, restart: bool, operator: &str, value: &str, data_type: DataType) {
+        let step = ScanStep {
+            operator: operator.to_string(),
+            value: value.to_string(),
+            data_type,
+        };
+        self.scan_history.push(restart, step, &self.search_results);
+    }
+
+    fn undo(&mut self) {
+        if let Some(results) = self.scan_history.undo() {
+            self.search_results = results;
+            self.value_history.clear();
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(results) = self.scan_history.redo() {
+            self.search_results = results;
+            self.value_history.clear();
+        }
+    }
+
+    /// Every search result with its current value.
+    fn result_records(&self) -> Vec<ExportRecord> {
+        let mut records = vec![];
+        for result in &self.search_results {
+            for pointer in &result.pointers {
+                let module =
+                    SearchRegion::module_address_in(&self.search_regions, result.pid, pointer);
+                records.push(ExportRecord {
+                    pid: result.pid,
+                    address: pointer,
+                    module: module.map(|module| (module.path, module.offset)),
+                    data_type: result.data_type.to_string(),
+                    value: read_value(result.pid, pointer, result.data_type)
+                        .map(|value| value.to_string())
+                        .unwrap_or_default(),
+                    description: String::new(),
+                });
+            }
+        }
+        records
+    }
+
+    fn tracked_records(&self) -> Vec<ExportRecord> {
+        self.tracked_addresses
+            .iter()
+            .map(|((pid, _), tracked)| ExportRecord {
+                pid: *pid,
+                address: tracked.address,
+                module: tracked
+                    .module
+                    .as_ref()
+                    .map(|module| (module.path.clone(), module.offset)),
+                data_type: tracked.value.data_type().to_string(),
+                value: tracked.value.to_string(),
+                description: tracked.description.clone(),
+            })
+            .collect()
+    }
+
+    /// Writes JSON for `.json` paths and CSV otherwise.
+    fn export(&self, path: &str, records: Vec<ExportRecord>) -> Result<(), AppError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        if path.ends_with(".json") {
+            export::write_json(&mut writer, &records)?;
+        } else {
+            export::write_csv(&mut writer, &records)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Adds the entries of a foreign table to the pending ones, module
+    /// offsets are looked up in the first selected process.
+    fn import_table(&mut self, path: &str) -> Result<(), AppError> {
+        let maps = match self.process_picker.selected_processes().into_iter().next() {
+            Some(process) => process_memory::regions(process.pid)?,
+            None => vec![],
+        };
+        let table = table_import::import_file(path, &maps)?;
+        self.pending_table.entries.extend(table.entries);
+        self.resolve_pending();
+        Ok(())
+    }
+
+    /// Writes the regions of the first selected process that are in the
+    /// search scope to a snapshot, along with the scan settings.
+    fn dump_process(&self, path: &str) -> Result<(), AppError> {
+        let Some(process) = self.process_picker.selected_processes().into_iter().next() else {
+            return Err(AppError::ProcessNotFound);
+        };
+        let filter = self.search_scope.filter(&self.region_filter);
+        let regions = process_memory::selected_regions(process.pid, &filter.compile()?)?;
+        let settings = serde_json::json!({
+            "search_scope": self.search_scope,
+            "region_filter": filter,
+            "data_type": self.data_type,
+            "endianness": self.endianness,
+            "search_buffer_size": self.settings.search_buffer_size,
+        });
+        process_memory::dump_process(
+            path,
+            &process,
+            regions,
+            settings.to_string(),
+            self.settings.compress_dumps,
+        )
+    }
+
+    /// Starts tracking the expression in `tracked_expression` in every
+    /// selected process.
+    fn track_expression(&mut self) -> Result<(), AppError> {
+        let location = Location::Expression(AddressExpr::parse(&self.tracked_expression)?);
+        for process in self.process_picker.selected_processes() {
+            let address = location.resolve(process.pid)?;
+            let value = read_value(process.pid, address, self.data_type)?;
+            self.tracked_addresses.insert(
+                (process.pid, location.clone()),
+                TrackedAddress::new(value, None, address),
+            );
+        }
+        Ok(())
+    }
+
+    /// Re-reads every tracked value, re-evaluating expressions.
+    fn refresh_tracked(&mut self) {
+        let tracked: Vec<_> = self
+            .tracked_addresses
+            .iter()
+            .map(|(key, tracked)| (key.clone(), tracked.value.data_type()))
+            .collect();
+        for (key, data_type) in tracked {
+            let (pid, location) = &key;
+            let frozen = self
+                .tracked_addresses
+                .get(&key)
+                .filter(|tracked| tracked.frozen)
+                .map(|tracked| tracked.value);
+            let value = location.resolve(*pid).and_then(|address| match frozen {
+                Some(value) => write_value(*pid, address, value).map(|_| (address, value)),
+                None => read_value(*pid, address, data_type).map(|value| (address, value)),
+            });
+            match (value, location) {
+                (Ok((address, value)), _) => {
+                    if let Some(tracked) = self.tracked_addresses.get_mut(&key) {
+                        tracked.value = value;
+                        tracked.address = address;
+                        tracked.error = None;
+                    }
+                }
+                (Err(AppError::ProcessNotFound), Location::Address(_))
+                    if self.tracked_addresses[&key].module.is_some() =>
+                {
+                    /* The process is gone, wait for it to be selected again */
+                    if let Some(tracked) = self.tracked_addresses.remove(&key) {
+                        self.pending_table.entries.push(tracked.to_entry(location));
+                    }
+                }
+                /* Unmapped for now, or the process exited */
+                (Err(err), _) => {
+                    if let Some(tracked) = self.tracked_addresses.get_mut(&key) {
+                        tracked.error = Some(err.to_string());
+                    }
+                }
+            }
+        }
+        self.resolve_pending();
+    }
 }
 
 impl eframe::App for TemplateApp {
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
         eframe::set_value(storage, eframe::APP_KEY, self);
+        eframe::set_value(storage, CHEAT_TABLE_KEY, &self.cheat_table());
     }
 
     /// Called each time the UI needs repainting, which may be many times per second.
@@ -112,6 +569,28 @@ impl eframe::App for TemplateApp {
                 let is_web = cfg!(target_arch = "wasm32");
                 if !is_web {
                     ui.menu_button("File", |ui| {
+                        for (label, action) in [
+                            ("Open Table...", FileAction::Open),
+                            ("Save Table...", FileAction::Save),
+                            ("Import Table...", FileAction::Import),
+                            ("Export Results...", FileAction::ExportResults),
+                            ("Export Tracked...", FileAction::ExportTracked),
+                            ("Open Session...", FileAction::OpenSession),
+                            ("Save Session...", FileAction::SaveSession),
+                            ("Open Core Dump...", FileAction::OpenCoreDump),
+                            ("Dump Process...", FileAction::DumpProcess),
+                            ("Open Snapshot...", FileAction::OpenSnapshot),
+                            ("Connect to Agent...", FileAction::ConnectAgent),
+                            ("Connect to GDB Stub...", FileAction::ConnectGdb),
+                        ] {
+                            if ui.button(label).clicked() {
+                                self.file_dialog = Some(FileDialog {
+                                    path: String::from(action.default_path()),
+                                    action,
+                                });
+                                ui.close_menu();
+                            }
+                        }
                         if ui.button("Quit").clicked() {
                             ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                         }
@@ -122,10 +601,15 @@ impl eframe::App for TemplateApp {
                 ui.button("Settings").clicked().then(|| {
                     self.settings.toggle();
                 });
+                ui.button("Pointer Scan").clicked().then(|| {
+                    self.pointer_scanner.toggle();
+                });
             });
         });
 
         self.settings.show(ctx);
+        self.pointer_scanner
+            .show(ctx, &self.settings, &mut self.error);
 
         egui::SidePanel::left("process_picker")
             .resizable(true)
@@ -146,7 +630,7 @@ impl eframe::App for TemplateApp {
                     ui.label(
                         self.search_results
                             .iter()
-                            .fold(0usize, |acc, (_, pointers)| acc + pointers.len())
+                            .fold(0usize, |acc, result| acc + result.pointers.len())
                             .to_string(),
                     );
                 });
@@ -155,36 +639,54 @@ impl eframe::App for TemplateApp {
                     .striped(true)
                     .cell_layout(Layout::left_to_right(Align::Center))
                     .column(Column::auto().at_least(50.0))
+                    .column(Column::auto().at_least(50.0))
                     .column(Column::remainder())
                     .sense(Sense::click())
                     .header(20.0, |mut header| {
                         header.col(|ui| {
                             ui.label("PID");
                         });
+                        header.col(|ui| {
+                            ui.label("Type");
+                        });
                         header.col(|ui| {
                             ui.label("Results");
                         });
                     })
                     .body(|mut body| {
-                        for (pid, pointers) in &self.search_results {
-                            for pointer in pointers.iter().take(10) {
+                        for result in &self.search_results {
+                            let pid = &result.pid;
+                            for pointer in result.pointers.iter().take(10) {
                                 body.row(20.0, |mut row| {
                                     row.col(|ui| {
                                         ui.label(format!("{pid}"));
                                     });
                                     row.col(|ui| {
-                                        ui.label(format!("{pointer:x}"));
+                                        ui.label(format!("{}", result.data_type));
+                                    });
+                                    let module = SearchRegion::module_address_in(
+                                        &self.search_regions,
+                                        *pid,
+                                        pointer,
+                                    );
+                                    row.col(|ui| {
+                                        match &module {
+                                            Some(module) => {
+                                                ui.label(format!("{pointer:x} ({module})"))
+                                            }
+                                            None => ui.label(format!("{pointer:x}")),
+                                        };
                                     });
 
                                     // Rows are selectable
-                                    let key = (*pid, *pointer);
+                                    let key = (*pid, Location::Address(pointer));
                                     row.set_selected(self.tracked_addresses.contains_key(&key));
                                     row.response().clicked().then(|| {
                                         if self.tracked_addresses.contains_key(&key) {
                                             self.tracked_addresses.remove(&key);
                                         } else {
                                             let value =
-                                                match read_value(*pid, *pointer, self.data_type) {
+                                                match read_value(*pid, pointer, result.data_type) {
                                                     Ok(value) => value,
                                                     Err(error) => {
                                                         eprintln!("Error: {error}");
@@ -192,7 +694,15 @@ impl eframe::App for TemplateApp {
                                                         return;
                                                     }
                                                 };
-                                            self.tracked_addresses.insert(key, value);
+                                            let module = module.clone().or_else(|| {
+                                                ModuleAddress::from_process(*pid, pointer)
+                                                    .ok()
+                                                    .flatten()
+                                            });
+                                            self.tracked_addresses.insert(
+                                                key.clone(),
+                                                TrackedAddress::new(value, module, pointer),
+                                            );
                                         }
                                     });
                                 });
@@ -215,16 +725,47 @@ impl eframe::App for TemplateApp {
             ui.horizontal(|ui| {
                 ui.button("Load Regions").clicked().then(|| {
                     let selected = self.process_picker.selected_processes();
-                    self.search_regions = SearchRegion::load(&selected, self.search_scope);
+                    match self.search_scope.filter(&self.region_filter).compile() {
+                        Ok(matcher) => {
+                            self.search_regions = SearchRegion::load(&selected, &matcher);
+                            self.resolve_pending();
+                        }
+                        Err(err) => {
+                            eprintln!("Error: {err}");
+                            self.error = Some(err);
+                        }
+                    }
                 });
                 self.search_scope.picker_for(ui);
             });
 
+            if self.search_scope == SearchScope::Custom {
+                ui.collapsing("Region Filter", |ui| {
+                    self.region_filter.editor(ui);
+                    RegionPreset::picker_for(
+                        ui,
+                        &mut self.settings.region_presets,
+                        &mut self.region_filter,
+                        &mut self.preset_name,
+                    );
+                });
+            }
+
             ui.horizontal(|ui| {
                 ui.label("Search regions: ");
                 ui.label(self.search_regions.len().to_string());
             });
 
+            if let Some(coverage) = &self.coverage {
+                ui.collapsing(coverage.summary(), |ui| {
+                    egui::ScrollArea::vertical().max_height(100.0).show(ui, |ui| {
+                        for (pid, range) in &coverage.unreadable {
+                            ui.monospace(format!("{}: {:X}-{:X}", pid, range.start, range.end));
+                        }
+                    });
+                });
+            }
+
             ui.horizontal(|ui| {
                 self.data_type.picker_for(ui);
             });
@@ -235,28 +776,45 @@ impl eframe::App for TemplateApp {
             });
             ui.horizontal(|ui| {
                 ui.button("Search").clicked().then(|| {
+                    let restart = self.search_results.is_empty();
+                    let mut coverage = ScanCoverage::default();
                     // Begin new search
-                    match if self.search_results.is_empty() {
-                        search::search_sync(
-                            &self.search_regions,
-                            self.data_type,
-                            &self.search,
-                            &self.settings,
-                            self.endianness,
-                        )
-                    }
-                    // Scan previous results
-                    else {
-                        search::search_continue_sync(
-                            &self.search_results,
-                            self.data_type,
-                            &self.search,
-                            &self.settings,
-                            self.endianness,
-                        )
-                    } {
+                    match self.while_stopped(|app| {
+                        if restart {
+                            let pid = |region: &SearchRegion| region.pid;
+                            search::by_process(&app.search_regions, pid, |source, regions| {
+                                search::search_sync(
+                                    source,
+                                    regions,
+                                    app.data_type,
+                                    &app.search,
+                                    &app.settings,
+                                    app.endianness,
+                                    &mut coverage,
+                                )
+                            })
+                        }
+                        // Scan previous results
+                        else {
+                            let pid = |result: &SearchResult| result.pid;
+                            search::by_process(&app.search_results, pid, |source, results| {
+                                search::search_continue_sync(
+                                    source,
+                                    results,
+                                    app.data_type,
+                                    &app.search,
+                                    app.endianness,
+                                    &mut coverage,
+                                )
+                            })
+                        }
+                    }) {
                         Ok(results) => {
                             self.search_results = results;
+                            self.coverage = Some(coverage);
+                            let operator = if restart { "search" } else { "refine" };
+                            let search = self.search.clone();
+                            self.record_step(restart, operator, &search, self.data_type);
                         }
                         Err(err) => {
                             eprintln!("Error: {err}");
@@ -267,9 +825,213 @@ impl eframe::App for TemplateApp {
 
                 ui.button("Clear").clicked().then(|| {
                     self.search_results.clear();
+                    self.value_history.clear();
+                    self.scan_history.clear();
+                });
+
+                ui.add_enabled(self.scan_history.can_undo(), egui::Button::new("Undo"))
+                    .clicked()
+                    .then(|| self.undo());
+                ui.add_enabled(self.scan_history.can_redo(), egui::Button::new("Redo"))
+                    .clicked()
+                    .then(|| self.redo());
+            });
+
+            ui.collapsing("Relative Search", |ui| {
+                relation_picker(&mut self.relation, ui);
+                ui.horizontal(|ui| {
+                    ui.label("Values:");
+                    ui.text_edit_singleline(&mut self.relative_values)
+                        .on_hover_text("Adjacent values separated by commas");
+                });
+                ui.horizontal(|ui| {
+                    ui.button("Sequence Search").clicked().then(|| {
+                        let mut coverage = ScanCoverage::default();
+                        match self.while_stopped(|app| {
+                            let values =
+                                search::parse_sequence(app.data_type, &app.relative_values)?;
+                            let pid = |region: &SearchRegion| region.pid;
+                            search::by_process(&app.search_regions, pid, |source, regions| {
+                                search::search_relative_sync(
+                                    source,
+                                    regions,
+                                    app.relation,
+                                    &values,
+                                    &app.settings,
+                                    app.endianness,
+                                    &mut coverage,
+                                )
+                            })
+                        }) {
+                            Ok(results) => {
+                                self.search_results = results;
+                                self.coverage = Some(coverage);
+                                self.value_history.clear();
+                                let operator = format!("sequence {:?}", self.relation);
+                                let values = self.relative_values.clone();
+                                self.record_step(true, &operator, &values, self.data_type);
+                            }
+                            Err(err) => {
+                                eprintln!("Error: {err}");
+                                self.error = Some(err);
+                            }
+                        }
+                    });
+                    ui.button("Same Delta As Last")
+                        .on_hover_text("Keep results that changed like they did last time")
+                        .clicked()
+                        .then(|| {
+                            match self.while_stopped(|app| {
+                                search::search_same_delta_sync(
+                                    &app.search_results,
+                                    app.relation,
+                                    &mut app.value_history,
+                                    app.endianness,
+                                )
+                            }) {
+                                Ok(results) => {
+                                    self.search_results = results;
+                                    let operator = format!("same delta {:?}", self.relation);
+                                    self.record_step(false, &operator, "", self.data_type);
+                                }
+                                Err(err) => {
+                                    eprintln!("Error: {err}");
+                                    self.error = Some(err);
+                                }
+                            }
+                        });
+                });
+            });
+
+            ui.collapsing("Changed Value Search", |ui| {
+                ui.horizontal(|ui| {
+                    ui.button("Snapshot")
+                        .on_hover_text("Remember the memory of the search regions")
+                        .clicked()
+                        .then(|| {
+                            let track_dirty_pages = self.settings.track_dirty_pages;
+                            match self.while_stopped(|app| {
+                                ChangeScan::capture(&app.search_regions, track_dirty_pages)
+                            }) {
+                                Ok(change_scan) => self.change_scan = Some(change_scan),
+                                Err(err) => {
+                                    eprintln!("Error: {err}");
+                                    self.error = Some(err);
+                                }
+                            }
+                        });
+                    for (label, changed) in [("Changed", true), ("Unchanged", false)] {
+                        let button = egui::Button::new(label);
+                        ui.add_enabled(self.change_scan.is_some(), button)
+                            .clicked()
+                            .then(|| {
+                                let restart = self.search_results.is_empty();
+                                match self.while_stopped(|app| {
+                                    let (results, data_type) = (&app.search_results, app.data_type);
+                                    match &mut app.change_scan {
+                                        Some(change_scan) => {
+                                            change_scan.scan(results, data_type, changed)
+                                        }
+                                        None => Ok(vec![]),
+                                    }
+                                }) {
+                                    Ok(results) => {
+                                        self.search_results = results;
+                                        let operator = label.to_lowercase();
+                                        self.record_step(restart, &operator, "", self.data_type);
+                                    }
+                                    Err(err) => {
+                                        eprintln!("Error: {err}");
+                                        self.error = Some(err);
+                                    }
+                                }
+                            });
+                    }
+                });
+            });
+
+            ui.collapsing("Grouped Search", |ui| {
+                let mut removed = None;
+                for (index, component) in self.group_components.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        component
+                            .data_type
+                            .picker_with_id(ui, ("group_type", index));
+                        ui.text_edit_singleline(&mut component.value);
+                        ui.button("Remove").clicked().then(|| removed = Some(index));
+                    });
+                }
+                if let Some(index) = removed {
+                    self.group_components.remove(index);
+                }
+                ui.button("Add Value").clicked().then(|| {
+                    self.group_components.push(GroupComponent {
+                        data_type: self.data_type,
+                        value: String::new(),
+                    });
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Within bytes:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.group_distance)
+                            .range(1..=search::MAX_GROUP_DISTANCE),
+                    );
+                    ui.checkbox(&mut self.group_ordered, "Fixed order");
+                });
+                ui.button("Grouped Search").clicked().then(|| {
+                    let mut coverage = ScanCoverage::default();
+                    match self.while_stopped(|app| {
+                        let pid = |region: &SearchRegion| region.pid;
+                        search::by_process(&app.search_regions, pid, |source, regions| {
+                            search::search_grouped_sync(
+                                source,
+                                regions,
+                                &app.group_components,
+                                search::GroupWindow {
+                                    distance: app.group_distance,
+                                    ordered: app.group_ordered,
+                                },
+                                &app.settings,
+                                app.endianness,
+                                &mut coverage,
+                            )
+                        })
+                    }) {
+                        Ok(results) => {
+                            self.search_results = results;
+                            self.coverage = Some(coverage);
+                            let values = self
+                                .group_components
+                                .iter()
+                                .map(|component| component.value.as_str())
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            self.record_step(true, "grouped", &values, self.data_type);
+                        }
+                        Err(err) => {
+                            eprintln!("Error: {err}");
+                            self.error = Some(err);
+                        }
+                    }
                 });
             });
 
+            egui::CollapsingHeader::new("Scan History")
+                .default_open(true)
+                .show(ui, |ui| {
+                    for (index, (step, applied)) in self.scan_history.entries().enumerate() {
+                        let text = format!(
+                            "{}. {} {} ({})",
+                            index + 1,
+                            step.operator,
+                            step.value,
+                            step.data_type
+                        );
+                        /* Steps that can be redone are greyed out */
+                        ui.add_enabled(applied, egui::Label::new(text));
+                    }
+                });
+
             ui.separator();
 
             for region in &self.search_regions {
@@ -283,31 +1045,72 @@ impl eframe::App for TemplateApp {
 
             ui.separator();
 
-            ui.button("Refresh").clicked().then(|| {
-                let keys: Vec<_> = self.tracked_addresses.keys().cloned().collect();
-                for key in keys {
-                    if let Ok(value) = read_value(key.0, key.1, self.data_type) {
-                        self.tracked_addresses.insert(key, value);
-                    } else {
-                        self.tracked_addresses.remove(&key);
+            ui.horizontal(|ui| {
+                ui.label("Expression:");
+                ui.text_edit_singleline(&mut self.tracked_expression);
+                ui.button("Track").clicked().then(|| {
+                    if let Err(err) = self.track_expression() {
+                        eprintln!("Error: {err}");
+                        self.error = Some(err);
                     }
-                }
+                });
             });
 
-            for ((pid, pointer), value) in &self.tracked_addresses {
+            ui.button("Refresh").clicked().then(|| {
+                self.refresh_tracked();
+            });
+
+            for ((pid, location), tracked) in &mut self.tracked_addresses {
+                let value = tracked.value;
                 ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut tracked.description)
+                            .hint_text("Description")
+                            .desired_width(120.0),
+                    );
                     ui.label(format!("PID: {}", pid));
-                    ui.label(format!("Pointer: {:#x}", pointer));
+                    match location {
+                        Location::Address(pointer) => {
+                            ui.label(format!("Pointer: {:#x}", pointer));
+                        }
+                        Location::Expression(expr) => {
+                            ui.label(format!("Expression: {expr}"));
+                            ui.label(format!("Resolved: {:#x}", tracked.address));
+                        }
+                    }
+                    if let Some(module) = &tracked.module {
+                        ui.label(format!("Module: {module}"));
+                    }
                     ui.label(format!("Type: {}", value.data_type()));
-                    ui.label(format!("Value: {value}"));
+                    match &tracked.error {
+                        Some(error) => {
+                            ui.label(format!("Value: unreadable, last {value}"));
+                            ui.colored_label(ui.visuals().error_fg_color, error);
+                        }
+                        None => {
+                            ui.label(format!("Value: {value}"));
+                        }
+                    }
+                    ui.checkbox(&mut tracked.frozen, "Freeze");
                     ui.button("Edit").clicked().then(|| {
                         self.editor = Some(Editor {
                             pid: *pid,
-                            pointer: *pointer,
+                            location: location.clone(),
                             data_type: value.data_type(),
-                            endianness: self.endianness,
                         });
                     });
+                    ui.button("Pointer Scan").clicked().then(|| {
+                        self.pointer_scanner.open(*pid, tracked.address);
+                    });
+                });
+            }
+
+            for entry in &self.pending_table.entries {
+                ui.horizontal(|ui| {
+                    ui.label(&entry.description);
+                    ui.label(format!("Location: {}", entry.location));
+                    ui.label(format!("Type: {}", entry.data_type));
+                    ui.label("Not found");
                 });
             }
 
@@ -326,6 +1129,77 @@ impl eframe::App for TemplateApp {
                     });
                 });
 
+            Window::new("File")
+                .resizable(false)
+                .collapsible(false)
+                .open(&mut self.file_dialog.is_some())
+                .show(ctx, |ui| {
+                    let Some(file_dialog) = &mut self.file_dialog else {
+                        return;
+                    };
+                    let (mut submit, mut cancel) = (false, false);
+                    ui.horizontal(|ui| {
+                        ui.label(match file_dialog.action {
+                            FileAction::ConnectAgent | FileAction::ConnectGdb => "Address:",
+                            _ => "Path:",
+                        });
+                        ui.text_edit_singleline(&mut file_dialog.path);
+                    });
+                    ui.horizontal(|ui| {
+                        let label = match file_dialog.action {
+                            FileAction::Open
+                            | FileAction::OpenSession
+                            | FileAction::OpenCoreDump
+                            | FileAction::OpenSnapshot => "Open",
+                            FileAction::Save | FileAction::SaveSession => "Save",
+                            FileAction::DumpProcess => "Dump",
+                            FileAction::Import => "Import",
+                            FileAction::ConnectAgent | FileAction::ConnectGdb => "Connect",
+                            FileAction::ExportResults | FileAction::ExportTracked => "Export",
+                        };
+                        submit = ui.button(label).clicked();
+                        cancel = ui.button("Cancel").clicked();
+                    });
+
+                    if submit {
+                        let FileDialog { path, action } = self.file_dialog.take().unwrap();
+                        let result = match action {
+                            FileAction::Open => self.open_table(&path),
+                            FileAction::Save => self.cheat_table().save(&path),
+                            FileAction::Import => self.import_table(&path),
+                            FileAction::ExportResults => self.export(&path, self.result_records()),
+                            FileAction::ExportTracked => self.export(&path, self.tracked_records()),
+                            FileAction::OpenSession => self.open_session(&path),
+                            FileAction::SaveSession => Session::capture(
+                                self.process_picker.processes(),
+                                &self.scan_history.steps(),
+                                &self.search_results,
+                            )
+                            .save(&path),
+                            FileAction::OpenCoreDump => process_memory::open_core_dump(&path)
+                                .map(|process| self.process_picker.add(process)),
+                            FileAction::DumpProcess => self.dump_process(&path),
+                            FileAction::OpenSnapshot => process_memory::open_snapshot(&path)
+                                .map(|process| self.process_picker.add(process)),
+                            FileAction::ConnectAgent => {
+                                process_memory::connect_agent(&path).map(|processes| {
+                                    for process in processes {
+                                        self.process_picker.add(process);
+                                    }
+                                })
+                            }
+                            FileAction::ConnectGdb => process_memory::connect_gdb(&path)
+                                .map(|process| self.process_picker.add(process)),
+                        };
+                        if let Err(err) = result {
+                            eprintln!("Error: {err}");
+                            self.error = Some(err);
+                        }
+                    } else if cancel {
+                        self.file_dialog = None;
+                    }
+                });
+
             Window::new("Editor")
                 .resizable(false)
                 .collapsible(false)
@@ -346,8 +1220,10 @@ impl eframe::App for TemplateApp {
                                         return;
                                     }
                                 };
-                                self.tracked_addresses
-                                    .insert((editor.pid, editor.pointer), value);
+                                let key = (editor.pid, editor.location.clone());
+                                if let Some(tracked) = self.tracked_addresses.get_mut(&key) {
+                                    tracked.value = value;
+                                }
                             }
                             self.editor = None;
                         });
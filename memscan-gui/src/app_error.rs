@@ -1,25 +1,27 @@
 use std::{
-    array::TryFromSliceError, fmt::Display, num::{ParseFloatError, ParseIntError}
+    array::TryFromSliceError,
+    fmt::Display,
+    num::{ParseFloatError, ParseIntError},
 };
 
 use nix::errno::Errno;
+use procfs::ProcError;
 
 pub enum AppError {
     PermissionDenied,
     ProcessNotFound,
     DataTypeParseError(String),
+    AddressParseError(String),
+    RegionFilterError(String),
+    /// A setting of the scan is outside of the range it supports
+    InvalidInput(String),
+    /// Dereference number `step` of an address expression failed
+    InvalidPointer {
+        step: usize,
+        address: u64,
+    },
     Errno(Errno),
-}
-
-impl AppError {
-    pub fn from_errno() -> Self {
-        let errno = Errno::last();
-        match errno {
-            nix::errno::Errno::EPERM => AppError::PermissionDenied,
-            nix::errno::Errno::ESRCH => AppError::ProcessNotFound,
-            _ => AppError::Errno(errno),
-        }
-    }
+    Io(String),
 }
 
 impl From<ParseIntError> for AppError {
@@ -40,6 +42,41 @@ impl From<TryFromSliceError> for AppError {
     }
 }
 
+impl From<regex::Error> for AppError {
+    fn from(err: regex::Error) -> Self {
+        AppError::RegionFilterError(err.to_string())
+    }
+}
+
+impl From<ProcError> for AppError {
+    fn from(err: ProcError) -> Self {
+        match err {
+            ProcError::PermissionDenied(_) => AppError::PermissionDenied,
+            ProcError::NotFound(_) => AppError::ProcessNotFound,
+            err => AppError::Io(err.to_string()),
+        }
+    }
+}
+
+impl From<Errno> for AppError {
+    fn from(err: Errno) -> Self {
+        match err {
+            Errno::EPERM => AppError::PermissionDenied,
+            Errno::ESRCH => AppError::ProcessNotFound,
+            errno => AppError::Errno(errno),
+        }
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(err: std::io::Error) -> Self {
+        match err.raw_os_error() {
+            Some(errno) => AppError::from(Errno::from_raw(errno)),
+            None => AppError::Io(err.to_string()),
+        }
+    }
+}
+
 impl Display for AppError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -48,7 +85,14 @@ impl Display for AppError {
             AppError::DataTypeParseError(input) => {
                 write!(f, "{}", input)
             }
+            AppError::AddressParseError(message) => write!(f, "{}", message),
+            AppError::RegionFilterError(message) => write!(f, "{}", message),
+            AppError::InvalidInput(message) => write!(f, "{}", message),
+            AppError::InvalidPointer { step, address } => {
+                write!(f, "Step {}: cannot read pointer at {:#x}", step, address)
+            }
             AppError::Errno(errno) => write!(f, "{}", errno),
+            AppError::Io(message) => write!(f, "{}", message),
         }
     }
 }
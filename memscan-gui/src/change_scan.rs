@@ -0,0 +1,310 @@
+use std::ops::Range;
+
+use memscan::{
+    address_set::AddressSet,
+    memory_source::{MemoryRegion, read_pages},
+    pagemap::{self, Pagemap},
+    snapshot::Snapshot,
+};
+
+use crate::{
+    app_error::AppError,
+    data_types::DataType,
+    process_memory,
+    process_stop::StoppedProcesses,
+    search::{SearchRegion, SearchResult},
+};
+
+/// Bytes compared at once when reading the memory again.
+const COMPARE_CHUNK: usize = 16 * 1024 * 1024;
+
+/// The memory of the searched processes as of the last snapshot or changed
+/// and unchanged scan, for values that can't be searched for directly.
+///
+/// With dirty page tracking the soft-dirty bits of live processes are cleared
+/// whenever their memory is read. The next scan then only reads the pages
+/// written in between, all others are known to be unchanged.
+pub struct ChangeScan {
+    snapshots: Vec<Snapshot>,
+    /// Processes whose soft-dirty bits were cleared with their snapshot
+    tracked: Vec<i32>,
+}
+
+impl ChangeScan {
+    pub fn capture(regions: &[SearchRegion], track_dirty_pages: bool) -> Result<Self, AppError> {
+        let mut pids: Vec<i32> = regions.iter().map(|region| region.pid).collect();
+        pids.sort_unstable();
+        pids.dedup();
+
+        let mut tracked = vec![];
+        let snapshots = pids
+            .into_iter()
+            .map(|pid| {
+                /* Cleared before reading, so writes during the capture count as dirty */
+                if track_dirty_pages && pid > 0 && pagemap::soft_dirty_supported() {
+                    pagemap::clear_soft_dirty(pid).map_err(|error| {
+                        AppError::Io(format!(
+                            "Failed to clear soft-dirty bits of PID {}: {}, turn off dirty page \
+                             tracking to compare every page",
+                            pid,
+                            AppError::from(error)
+                        ))
+                    })?;
+                    tracked.push(pid);
+                }
+                let regions = regions
+                    .iter()
+                    .filter(|region| region.pid == pid)
+                    .map(|region| MemoryRegion {
+                        start: region.start,
+                        end: region.end,
+                        readable: true,
+                        writable: true,
+                        executable: false,
+                        shared: false,
+                        pathname: region.pathname.clone(),
+                        offset: region.offset,
+                    })
+                    .collect();
+                let source = process_memory::source(pid);
+                Ok(Snapshot::capture(
+                    &*source,
+                    pid,
+                    String::new(),
+                    String::new(),
+                    regions,
+                )?)
+            })
+            .collect::<Result<_, AppError>>()?;
+
+        Ok(Self { snapshots, tracked })
+    }
+
+    /// Reads the memory again and returns what changed, for every snapshot.
+    fn refresh(&mut self) -> Result<Vec<Changes>, AppError> {
+        let mut buffer = vec![0u8; COMPARE_CHUNK];
+        self.snapshots
+            .iter_mut()
+            .map(|snapshot| {
+                let dirty = self
+                    .tracked
+                    .contains(&snapshot.pid)
+                    .then(|| dirty_ranges(snapshot))
+                    .flatten();
+                let source = process_memory::source(snapshot.pid);
+                let mut changes = Changes::default();
+                for (index, captured) in snapshot.regions.iter_mut().enumerate() {
+                    let start = captured.region.start;
+                    let whole = start..start + captured.bytes.len() as u64;
+                    let ranges = match &dirty {
+                        Some(dirty) => dirty[index].clone(),
+                        None => vec![whole],
+                    };
+                    for range in ranges {
+                        for chunk_start in range.clone().step_by(COMPARE_CHUNK) {
+                            let length = ((range.end - chunk_start) as usize).min(COMPARE_CHUNK);
+                            let buffer = &mut buffer[..length];
+                            let offset = (chunk_start - start) as usize;
+                            let mut compared = 0;
+                            for part in
+                                read_pages(&*source, chunk_start, buffer, pagemap::page_size())?
+                            {
+                                /* Holes of the snapshot have no previous value */
+                                let part = offset + part.start..offset + part.end;
+                                for known in captured.captured(part) {
+                                    let known = known.start - offset..known.end - offset;
+                                    changes.unreadable(chunk_start, compared..known.start);
+                                    let previous = &mut captured.bytes[offset..offset + length];
+                                    for position in known.clone() {
+                                        if previous[position] != buffer[position] {
+                                            changes.bytes.push(chunk_start + position as u64);
+                                            previous[position] = buffer[position];
+                                        }
+                                    }
+                                    compared = known.end;
+                                }
+                            }
+                            changes.unreadable(chunk_start, compared..length);
+                        }
+                    }
+                }
+                Ok(changes)
+            })
+            .collect()
+    }
+
+    /// Keeps the `results` whose value changed, or didn't, since the last
+    /// scan. Without results every address of the snapshot aligned for
+    /// `data_type` is a candidate. Values that can't be read any more are
+    /// dropped.
+    pub fn scan(
+        &mut self,
+        results: &[SearchResult],
+        data_type: DataType,
+        changed: bool,
+    ) -> Result<Vec<SearchResult>, AppError> {
+        if results.is_empty() && data_type == DataType::Any {
+            return Err(AppError::DataTypeParseError(
+                "Pick a data type to compare snapshots".to_string(),
+            ));
+        }
+
+        let changes = self.refresh()?;
+        let mut new_results = vec![];
+        if results.is_empty() {
+            let size = data_type.size() as u64;
+            let alignment = if matches!(data_type, DataType::U16Packed | DataType::U24) {
+                1
+            } else {
+                size
+            };
+            for (snapshot, changes) in self.snapshots.iter().zip(&changes) {
+                let candidates = snapshot.regions.iter().flat_map(|captured| {
+                    let start = captured.region.start;
+                    let end = (start + captured.bytes.len() as u64).saturating_sub(size - 1);
+                    (start.next_multiple_of(alignment)..end).step_by(alignment as usize)
+                });
+                let pointers = filter_changed(candidates, changes, size, changed);
+                if !pointers.is_empty() {
+                    new_results.push(SearchResult {
+                        pid: snapshot.pid,
+                        data_type,
+                        pointers,
+                    });
+                }
+            }
+        } else {
+            for result in results {
+                let Some(index) = self
+                    .snapshots
+                    .iter()
+                    .position(|snapshot| snapshot.pid == result.pid)
+                else {
+                    return Err(AppError::Io(format!(
+                        "No snapshot of PID {}, take a new snapshot",
+                        result.pid
+                    )));
+                };
+                let size = result.data_type.size() as u64;
+                let pointers =
+                    filter_changed(result.pointers.iter(), &changes[index], size, changed);
+                if !pointers.is_empty() {
+                    new_results.push(SearchResult {
+                        pid: result.pid,
+                        data_type: result.data_type,
+                        pointers,
+                    });
+                }
+            }
+        }
+        Ok(new_results)
+    }
+}
+
+/// What changed in the memory of one snapshot since it was last read.
+#[derive(Default)]
+struct Changes {
+    /// Addresses of the bytes that changed
+    bytes: AddressSet,
+    /// Ascending ranges that couldn't be read again, whose values are
+    /// neither changed nor unchanged
+    unreadable: Vec<Range<u64>>,
+}
+
+impl Changes {
+    /// Records that `range` of the chunk at `chunk_start` wasn't read.
+    fn unreadable(&mut self, chunk_start: u64, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        let range = chunk_start + range.start as u64..chunk_start + range.end as u64;
+        match self.unreadable.last_mut() {
+            Some(last) if last.end == range.start => last.end = range.end,
+            _ => self.unreadable.push(range),
+        }
+    }
+}
+
+/// The pages of every region written since the soft-dirty bits were cleared,
+/// which clears them again. `None` if the pagemap can't be read.
+fn dirty_ranges(snapshot: &Snapshot) -> Option<Vec<Vec<Range<u64>>>> {
+    /* A page written between reading the pagemap and clearing it would be
+    neither compared now nor dirty next time, so the process stays stopped in
+    between. Writes after the clear are compared next time */
+    let stopped = match StoppedProcesses::stop([snapshot.pid]) {
+        Ok(stopped) => stopped,
+        Err(error) => {
+            println!(
+                "[WARN]: Failed to stop PID {} to read its dirty pages, comparing every page: {}",
+                snapshot.pid, error
+            );
+            return None;
+        }
+    };
+    let pagemap = Pagemap::open(snapshot.pid).ok()?;
+    let dirty = snapshot
+        .regions
+        .iter()
+        .map(|captured| {
+            let start = captured.region.start;
+            pagemap.soft_dirty(start, start + captured.bytes.len() as u64)
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+    pagemap::clear_soft_dirty(snapshot.pid).ok()?;
+    drop(stopped);
+    Some(dirty)
+}
+
+/// The ascending `candidates` of which one of the `size` bytes changed, or of
+/// which none did if not `changed`. Candidates that couldn't be read are
+/// left out either way.
+fn filter_changed(
+    candidates: impl Iterator<Item = u64>,
+    changes: &Changes,
+    size: u64,
+    changed: bool,
+) -> AddressSet {
+    let mut changed_bytes = changes.bytes.iter().peekable();
+    let mut unreadable = changes.unreadable.iter().peekable();
+    let mut pointers = AddressSet::new();
+    for address in candidates {
+        while changed_bytes.next_if(|&byte| byte < address).is_some() {}
+        while unreadable.next_if(|range| range.end <= address).is_some() {}
+        if unreadable
+            .peek()
+            .is_some_and(|range| range.start < address + size)
+        {
+            continue;
+        }
+        let is_changed = changed_bytes
+            .peek()
+            .is_some_and(|&byte| byte < address + size);
+        if is_changed == changed {
+            pointers.push(address);
+        }
+    }
+    pointers
+}
+
+#[test]
+fn test_filter_changed() {
+    let mut changes = Changes {
+        bytes: [0x1005, 0x1011].into_iter().collect(),
+        unreadable: vec![],
+    };
+    changes.unreadable(0x1000, 0x20..0x28);
+    changes.unreadable(0x1000, 0x28..0x30);
+    assert_eq!(changes.unreadable, vec![0x1020..0x1030]);
+
+    let candidates = || (0x1000..0x1040).step_by(4);
+    let changed = filter_changed(candidates(), &changes, 4, true);
+    assert_eq!(changed.iter().collect::<Vec<_>>(), [0x1004, 0x1010]);
+    let unchanged = filter_changed(candidates(), &changes, 4, false);
+    assert_eq!(
+        unchanged.iter().collect::<Vec<_>>(),
+        [
+            0x1000, 0x1008, 0x100c, 0x1014, 0x1018, 0x101c, 0x1030, 0x1034, 0x1038, 0x103c
+        ]
+    );
+}
@@ -0,0 +1,95 @@
+use std::{fmt::Display, fs::File, io::BufReader, path::Path};
+
+use crate::{app_error::AppError, data_types::DataType, module_address::ModuleAddress};
+
+pub const CHEAT_TABLE_VERSION: u32 = 1;
+
+/// Tracked addresses saved to share or reapply later, stored as JSON:
+///
+/// ```json
+/// {
+///   "version": 1,
+///   "process": "game",
+///   "entries": [
+///     {
+///       "description": "Health",
+///       "data_type": "I32",
+///       "location": { "Module": { "path": "/opt/game/game", "offset": 1234 } },
+///       "frozen": true,
+///       "value": "100"
+///     }
+///   ]
+/// }
+/// ```
+///
+/// `process` is the command name entries are applied to when regions are
+/// loaded, empty for any selected process. `value` is only used for frozen
+/// entries.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+pub struct CheatTable {
+    pub version: u32,
+    pub process: String,
+    pub entries: Vec<CheatEntry>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct CheatEntry {
+    pub description: String,
+    pub data_type: DataType,
+    pub location: TableLocation,
+    pub frozen: bool,
+    pub value: Option<String>,
+}
+
+/// How an entry is found again, see `Location` for the tracked counterpart.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub enum TableLocation {
+    /// Only meaningful for the same process instance
+    Address(u64),
+    Module(ModuleAddress),
+    /// An `AddressExpr` such as `[libgame.so+0x1A2B0]+0x18`
+    Expression(String),
+}
+
+impl Display for TableLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TableLocation::Address(address) => write!(f, "{:#x}", address),
+            TableLocation::Module(module) => write!(f, "{}", module),
+            TableLocation::Expression(expr) => write!(f, "{}", expr),
+        }
+    }
+}
+
+impl CheatTable {
+    pub fn new(process: String, entries: Vec<CheatEntry>) -> Self {
+        Self {
+            version: CHEAT_TABLE_VERSION,
+            process,
+            entries,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AppError> {
+        let reader = BufReader::new(File::open(path)?);
+        let table: CheatTable =
+            serde_json::from_reader(reader).map_err(|err| AppError::Io(err.to_string()))?;
+        if table.version > CHEAT_TABLE_VERSION {
+            return Err(AppError::Io(format!(
+                "Cheat table version {} is newer than the supported version {}",
+                table.version, CHEAT_TABLE_VERSION
+            )));
+        }
+        Ok(table)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), AppError> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self).map_err(|err| AppError::Io(err.to_string()))
+    }
+
+    /// Whether the entries should be applied to a process named `cmd`.
+    pub fn applies_to(&self, cmd: &str) -> bool {
+        self.process.is_empty() || self.process == cmd
+    }
+}
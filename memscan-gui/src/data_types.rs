@@ -1,62 +1,145 @@
 use std::fmt::Display;
 
 use egui::ComboBox;
-use memscan::search::MemorySearch;
+use memscan::search::{
+    MemorySearch,
+    packed::PackedSearch,
+    relative::{self, Relation, RelativeSearch},
+};
 use serde::de::value;
 
-use crate::{app_error::AppError, endian::Endianness};
+use crate::{
+    app_error::AppError,
+    endian::Endianness,
+    fixed_point::{FixedPoint, FixedValue},
+};
 
-#[derive(Debug, PartialEq, Default, Clone, Copy, serde::Deserialize, serde::Serialize)]
+const U24_MAX: u32 = 0xFF_FFFF;
+
+#[derive(Debug, PartialEq, Eq, Hash, Default, Clone, Copy, serde::Deserialize, serde::Serialize)]
 pub enum DataType {
     U8,
     U16,
+    /// 16 bit integer at any byte offset, like in packed structs
+    U16Packed,
+    U24,
     U32,
     #[default]
     U64,
-    // S8,
-    // S16,
-    // S32,
-    // S64,
+    U128,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
     F32,
     F64,
+    Fixed(FixedPoint),
+    /// Every integer and float type at once, results carry the matched type
+    Any,
 }
 
 impl Display for DataType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            DataType::Fixed(format) => write!(f, "{}", format),
+            _ => write!(f, "{:?}", self),
+        }
     }
 }
 
+fn relative_unsupported(data_type: DataType) -> AppError {
+    AppError::DataTypeParseError(format!(
+        "Relative search is not supported for {:?}",
+        data_type
+    ))
+}
+
 impl DataType {
+    /// Fails for the types relative and same delta searches don't support,
+    /// so that is known before any memory is read.
+    pub fn check_relative(self) -> Result<(), AppError> {
+        match self {
+            DataType::U8
+            | DataType::U16
+            | DataType::U32
+            | DataType::U64
+            | DataType::I8
+            | DataType::I16
+            | DataType::I32
+            | DataType::I64 => Ok(()),
+            data_type => Err(relative_unsupported(data_type)),
+        }
+    }
+
     pub fn picker_for(&mut self, ui: &mut egui::Ui) {
-        ComboBox::from_label("Datatype")
-            .selected_text(format!("{:?}", self))
+        self.picker_with(ui, ComboBox::from_label("Datatype"));
+    }
+
+    pub fn picker_with_id(&mut self, ui: &mut egui::Ui, id_salt: impl std::hash::Hash) {
+        self.picker_with(ui, ComboBox::from_id_salt(id_salt));
+    }
+
+    fn picker_with(&mut self, ui: &mut egui::Ui, combo_box: ComboBox) {
+        combo_box
+            .selected_text(format!("{}", self))
             .show_ui(ui, |ui| {
                 ui.selectable_value(self, Self::U8, "u8");
                 ui.selectable_value(self, Self::U16, "u16");
+                ui.selectable_value(self, Self::U16Packed, "u16 packed");
+                ui.selectable_value(self, Self::U24, "u24");
                 ui.selectable_value(self, Self::U32, "u32");
                 ui.selectable_value(self, Self::U64, "u64");
-                // ui.selectable_value(self, Self::S8, "s8");
-                // ui.selectable_value(self, Self::S16, "s16");
-                // ui.selectable_value(self, Self::S32, "s32");
-                // ui.selectable_value(self, Self::S64, "s64");
+                ui.selectable_value(self, Self::U128, "u128");
+                ui.selectable_value(self, Self::I8, "i8");
+                ui.selectable_value(self, Self::I16, "i16");
+                ui.selectable_value(self, Self::I32, "i32");
+                ui.selectable_value(self, Self::I64, "i64");
+                ui.selectable_value(self, Self::I128, "i128");
                 ui.selectable_value(self, Self::F32, "f32");
                 ui.selectable_value(self, Self::F64, "f64");
+                for format in [
+                    FixedPoint::Q8_8,
+                    FixedPoint::UQ8_8,
+                    FixedPoint::Q16_16,
+                    FixedPoint::UQ16_16,
+                    FixedPoint::Q24_8,
+                    FixedPoint::UQ24_8,
+                ] {
+                    ui.selectable_value(self, Self::Fixed(format), format.to_string());
+                }
+                ui.selectable_value(self, Self::Any, "any");
+                if ui
+                    .selectable_label(matches!(self, Self::Fixed(FixedPoint::Scaled(_))), "scaled")
+                    .clicked()
+                {
+                    *self = Self::Fixed(FixedPoint::Scaled(100));
+                }
             });
+        if let Self::Fixed(FixedPoint::Scaled(factor)) = self {
+            ui.add(egui::DragValue::new(factor).range(1..=u32::MAX).prefix("x"));
+        }
     }
 
     pub fn size(self) -> usize {
         match self {
             Self::U8 => 1,
             Self::U16 => 2,
+            Self::U16Packed => 2,
+            Self::U24 => 3,
             Self::U32 => 4,
             Self::U64 => 8,
-            // Self::S8 => 1,
-            // Self::S16 => 2,
-            // Self::S32 => 4,
-            // Self::S64 => 8,
+            Self::U128 => 16,
+            Self::I8 => 1,
+            Self::I16 => 2,
+            Self::I32 => 4,
+            Self::I64 => 8,
+            Self::I128 => 16,
             Self::F32 => 4,
             Self::F64 => 8,
+            Self::Fixed(format) => format.size(),
+            /* Largest candidate */
+            Self::Any => 8,
         }
     }
 
@@ -64,19 +147,80 @@ impl DataType {
         let result = match self {
             Self::U8 => WrappedValue::U8(label.parse()?),
             Self::U16 => WrappedValue::U16(label.parse()?),
+            Self::U16Packed => WrappedValue::U16Packed(label.parse()?),
+            Self::U24 => {
+                let value = label.parse()?;
+                if value > U24_MAX {
+                    return Err(AppError::DataTypeParseError(format!(
+                        "{} is too large for u24",
+                        value
+                    )));
+                }
+                WrappedValue::U24(value)
+            }
             Self::U32 => WrappedValue::U32(label.parse()?),
             Self::U64 => WrappedValue::U64(label.parse()?),
-            // Self::S8 => WrappedValue::S8(label.parse()?),
-            // Self::S16 => WrappedValue::S16(label.parse()?),
-            // Self::S32 => WrappedValue::S32(label.parse()?),
-            // Self::S64 => WrappedValue::S64(label.parse()?),
+            Self::U128 => WrappedValue::U128(label.parse()?),
+            Self::I8 => WrappedValue::I8(label.parse()?),
+            Self::I16 => WrappedValue::I16(label.parse()?),
+            Self::I32 => WrappedValue::I32(label.parse()?),
+            Self::I64 => WrappedValue::I64(label.parse()?),
+            Self::I128 => WrappedValue::I128(label.parse()?),
             Self::F32 => WrappedValue::F32(label.parse()?),
             Self::F64 => WrappedValue::F64(label.parse()?),
+            Self::Fixed(format) => WrappedValue::Fixed(format.parse(label)?),
+            Self::Any => {
+                return Err(AppError::DataTypeParseError(
+                    "Any has to be resolved to a concrete type".to_string(),
+                ));
+            }
         };
 
         Ok(result)
     }
 
+    /// Parses the input for every type this stands for. Any skips the types
+    /// the input does not fit and only tries signed types for negative input,
+    /// as they would find the same addresses as their unsigned counterparts.
+    pub fn parse_candidates(self, label: &str) -> Result<Vec<WrappedValue>, AppError> {
+        if self != Self::Any {
+            return Ok(vec![self.parse(label)?]);
+        }
+
+        let negative = label.trim_start().starts_with('-');
+        let candidates: Vec<WrappedValue> = [
+            Self::U8,
+            Self::U16,
+            Self::U32,
+            Self::U64,
+            Self::I8,
+            Self::I16,
+            Self::I32,
+            Self::I64,
+            Self::F32,
+            Self::F64,
+        ]
+        .into_iter()
+        .filter(|data_type| negative || !data_type.is_signed_integer())
+        .filter_map(|data_type| data_type.parse(label).ok())
+        .collect();
+
+        if candidates.is_empty() {
+            return Err(AppError::DataTypeParseError(format!(
+                "{} is not a number",
+                label
+            )));
+        }
+        Ok(candidates)
+    }
+
+    fn is_signed_integer(self) -> bool {
+        matches!(
+            self,
+            Self::I8 | Self::I16 | Self::I32 | Self::I64 | Self::I128
+        )
+    }
+
     pub fn cast(self, buffer: &[u8], endianness: Endianness) -> Result<WrappedValue, AppError> {
         if buffer.len() < self.size() {
             return Err(AppError::DataTypeParseError(format!(
@@ -84,27 +228,53 @@ impl DataType {
                 self
             )));
         }
-        match endianness {
-            Endianness::Little => {
-                panic!("Big endian not implemented yet");
-            }
-            Endianness::Big => {
-                panic!("Big endian not implemented yet");
-            }
-            Endianness::Native => {
-                match self {
-                    Self::U8 => Ok(WrappedValue::U8(buffer[0])),
-                    Self::U16 => Ok(WrappedValue::U16(u16::from_ne_bytes(buffer.try_into()?))),
-                    Self::U32 => Ok(WrappedValue::U32(u32::from_ne_bytes(buffer.try_into()?))),
-                    Self::U64 => Ok(WrappedValue::U64(u64::from_ne_bytes(buffer.try_into()?))),
-                    // Self::S8 => Ok(WrappedValue::S8(buffer[0])),
-                    // Self::S16 => Ok(WrappedValue::S16(i16::from_ne_bytes(buffer.try_into().unwrap()))),
-                    // Self::S32 => Ok(WrappedValue::S32(i32::from_ne_bytes(buffer.try_into().unwrap()))),
-                    // Self::S64 => Ok(WrappedValue::S64(i64::from_ne_bytes(buffer.try_into().unwrap()))),
-                    Self::F32 => Ok(WrappedValue::F32(f32::from_ne_bytes(buffer.try_into()?))),
-                    Self::F64 => Ok(WrappedValue::F64(f64::from_ne_bytes(buffer.try_into()?))),
-                }
-            }
+        if self == Self::Any {
+            return Err(AppError::DataTypeParseError(
+                "Any has to be resolved to a concrete type".to_string(),
+            ));
+        }
+        if let Self::Fixed(format) = self {
+            let raw = format.read(buffer, endianness);
+            return Ok(WrappedValue::Fixed(FixedValue {
+                format,
+                raw,
+                lower: raw,
+                upper: raw,
+            }));
+        }
+        if self == Self::U16Packed {
+            /* Packed integers are little endian unless big is requested */
+            return Ok(WrappedValue::U16Packed(match endianness {
+                Endianness::Big => u16::from_be_bytes([buffer[0], buffer[1]]),
+                _ => u16::from_le_bytes([buffer[0], buffer[1]]),
+            }));
+        }
+        if self == Self::U24 {
+            /* Packed 24 bit integers are little endian unless big is requested */
+            return Ok(WrappedValue::U24(match endianness {
+                Endianness::Big => u32::from_be_bytes([0, buffer[0], buffer[1], buffer[2]]),
+                _ => u32::from_le_bytes([buffer[0], buffer[1], buffer[2], 0]),
+            }));
+        }
+        let mut bytes = buffer[..self.size()].to_vec();
+        if endianness.swaps() {
+            bytes.reverse();
+        }
+        let buffer = bytes.as_slice();
+        match self {
+            Self::U8 => Ok(WrappedValue::U8(buffer[0])),
+            Self::U16 => Ok(WrappedValue::U16(u16::from_ne_bytes(buffer.try_into()?))),
+            Self::U32 => Ok(WrappedValue::U32(u32::from_ne_bytes(buffer.try_into()?))),
+            Self::U64 => Ok(WrappedValue::U64(u64::from_ne_bytes(buffer.try_into()?))),
+            Self::U128 => Ok(WrappedValue::U128(u128::from_ne_bytes(buffer.try_into()?))),
+            Self::I8 => Ok(WrappedValue::I8(buffer[0] as i8)),
+            Self::I16 => Ok(WrappedValue::I16(i16::from_ne_bytes(buffer.try_into()?))),
+            Self::I32 => Ok(WrappedValue::I32(i32::from_ne_bytes(buffer.try_into()?))),
+            Self::I64 => Ok(WrappedValue::I64(i64::from_ne_bytes(buffer.try_into()?))),
+            Self::I128 => Ok(WrappedValue::I128(i128::from_ne_bytes(buffer.try_into()?))),
+            Self::F32 => Ok(WrappedValue::F32(f32::from_ne_bytes(buffer.try_into()?))),
+            Self::F64 => Ok(WrappedValue::F64(f64::from_ne_bytes(buffer.try_into()?))),
+            Self::U16Packed | Self::U24 | Self::Fixed(_) | Self::Any => unreachable!(),
         }
     }
 }
@@ -113,14 +283,21 @@ impl DataType {
 pub enum WrappedValue {
     U8(u8),
     U16(u16),
+    /// Unaligned 16 bit integer, stored little endian unless requested otherwise
+    U16Packed(u16),
+    /// Packed 24 bit integer, stored little endian unless requested otherwise
+    U24(u32),
     U32(u32),
     U64(u64),
-    // S8(i8),
-    // S16(i16),
-    // S32(i32),
-    // S64(i64),
+    U128(u128),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
     F32(f32),
     F64(f64),
+    Fixed(FixedValue),
 }
 
 impl WrappedValue {
@@ -128,58 +305,117 @@ impl WrappedValue {
         match self {
             WrappedValue::U8(_) => DataType::U8,
             WrappedValue::U16(_) => DataType::U16,
+            WrappedValue::U16Packed(_) => DataType::U16Packed,
+            WrappedValue::U24(_) => DataType::U24,
             WrappedValue::U32(_) => DataType::U32,
             WrappedValue::U64(_) => DataType::U64,
-            // WrappedValue::S8(_) => DataType::S8,
-            // WrappedValue::S16(_) => DataType::S16,
-            // WrappedValue::S32(_) => DataType::S32,
-            // WrappedValue::S64(_) => DataType::S64,
+            WrappedValue::U128(_) => DataType::U128,
+            WrappedValue::I8(_) => DataType::I8,
+            WrappedValue::I16(_) => DataType::I16,
+            WrappedValue::I32(_) => DataType::I32,
+            WrappedValue::I64(_) => DataType::I64,
+            WrappedValue::I128(_) => DataType::I128,
             WrappedValue::F32(_) => DataType::F32,
             WrappedValue::F64(_) => DataType::F64,
+            WrappedValue::Fixed(value) => DataType::Fixed(value.format),
         }
     }
 
     pub fn compare_to(self, buffer: &[u8], endianness: Endianness) -> bool {
+        if let WrappedValue::Fixed(value) = self {
+            return value.contains(value.format.read(buffer, endianness));
+        }
         match endianness {
             Endianness::Little => match self {
                 WrappedValue::U8(value) => buffer[0] == value,
                 WrappedValue::U16(value) => buffer[0..2] == value.to_le_bytes(),
+                WrappedValue::U16Packed(_) => buffer[0..2] == self.packed_bytes(endianness),
+                WrappedValue::U24(_) => buffer[0..3] == self.packed_bytes(endianness),
                 WrappedValue::U32(value) => buffer[0..4] == value.to_le_bytes(),
                 WrappedValue::U64(value) => buffer[0..8] == value.to_le_bytes(),
-                // WrappedValue::S8(value) => buffer[0] == value as u8,
-                // WrappedValue::S16(value) => buffer[0..2] == value.to_le_bytes(),
-                // WrappedValue::S32(value) => buffer[0..4] == value.to_le_bytes(),
-                // WrappedValue::S64(value) => buffer[0..8] == value.to_le_bytes(),
+                WrappedValue::U128(value) => buffer[0..16] == value.to_le_bytes(),
+                WrappedValue::I8(value) => buffer[0] == value as u8,
+                WrappedValue::I16(value) => buffer[0..2] == value.to_le_bytes(),
+                WrappedValue::I32(value) => buffer[0..4] == value.to_le_bytes(),
+                WrappedValue::I64(value) => buffer[0..8] == value.to_le_bytes(),
+                WrappedValue::I128(value) => buffer[0..16] == value.to_le_bytes(),
                 WrappedValue::F32(value) => buffer[0..4] == value.to_le_bytes(),
                 WrappedValue::F64(value) => buffer[0..8] == value.to_le_bytes(),
+                WrappedValue::Fixed(_) => unreachable!(),
             },
             Endianness::Big => match self {
                 WrappedValue::U8(value) => buffer[0] == value,
                 WrappedValue::U16(value) => buffer[0..2] == value.to_be_bytes(),
+                WrappedValue::U16Packed(_) => buffer[0..2] == self.packed_bytes(endianness),
+                WrappedValue::U24(_) => buffer[0..3] == self.packed_bytes(endianness),
                 WrappedValue::U32(value) => buffer[0..4] == value.to_be_bytes(),
                 WrappedValue::U64(value) => buffer[0..8] == value.to_be_bytes(),
-                // WrappedValue::S8(value) => buffer[0] == value as u8,
-                // WrappedValue::S16(value) => buffer[0..2] == value.to_be_bytes(),
-                // WrappedValue::S32(value) => buffer[0..4] == value.to_be_bytes(),
-                // WrappedValue::S64(value) => buffer[0..8] == value.to_be_bytes(),
+                WrappedValue::U128(value) => buffer[0..16] == value.to_be_bytes(),
+                WrappedValue::I8(value) => buffer[0] == value as u8,
+                WrappedValue::I16(value) => buffer[0..2] == value.to_be_bytes(),
+                WrappedValue::I32(value) => buffer[0..4] == value.to_be_bytes(),
+                WrappedValue::I64(value) => buffer[0..8] == value.to_be_bytes(),
+                WrappedValue::I128(value) => buffer[0..16] == value.to_be_bytes(),
                 WrappedValue::F32(value) => buffer[0..4] == value.to_be_bytes(),
                 WrappedValue::F64(value) => buffer[0..8] == value.to_be_bytes(),
+                WrappedValue::Fixed(_) => unreachable!(),
             },
             Endianness::Native => match self {
                 WrappedValue::U8(value) => buffer[0] == value,
                 WrappedValue::U16(value) => buffer[0..2] == value.to_ne_bytes(),
+                WrappedValue::U16Packed(_) => buffer[0..2] == self.packed_bytes(endianness),
+                WrappedValue::U24(_) => buffer[0..3] == self.packed_bytes(endianness),
                 WrappedValue::U32(value) => buffer[0..4] == value.to_ne_bytes(),
                 WrappedValue::U64(value) => buffer[0..8] == value.to_ne_bytes(),
-                // WrappedValue::S8(value) => buffer[0] == value as u8,
-                // WrappedValue::S16(value) => buffer[0..2] == value.to_ne_bytes(),
-                // WrappedValue::S32(value) => buffer[0..4] == value.to_ne_bytes(),
-                // WrappedValue::S64(value) => buffer[0..8] == value.to_ne_bytes(),
+                WrappedValue::U128(value) => buffer[0..16] == value.to_ne_bytes(),
+                WrappedValue::I8(value) => buffer[0] == value as u8,
+                WrappedValue::I16(value) => buffer[0..2] == value.to_ne_bytes(),
+                WrappedValue::I32(value) => buffer[0..4] == value.to_ne_bytes(),
+                WrappedValue::I64(value) => buffer[0..8] == value.to_ne_bytes(),
+                WrappedValue::I128(value) => buffer[0..16] == value.to_ne_bytes(),
                 WrappedValue::F32(value) => buffer[0..4] == value.to_ne_bytes(),
                 WrappedValue::F64(value) => buffer[0..8] == value.to_ne_bytes(),
+                WrappedValue::Fixed(_) => unreachable!(),
             },
         }
     }
 
+    /// Native byte representation, as written back into memory.
+    pub fn to_ne_bytes(self) -> Vec<u8> {
+        match self {
+            WrappedValue::U8(value) => vec![value],
+            WrappedValue::U16(value) => value.to_ne_bytes().to_vec(),
+            WrappedValue::U16Packed(_) | WrappedValue::U24(_) => {
+                self.packed_bytes(Endianness::Native)
+            }
+            WrappedValue::U32(value) => value.to_ne_bytes().to_vec(),
+            WrappedValue::U64(value) => value.to_ne_bytes().to_vec(),
+            WrappedValue::U128(value) => value.to_ne_bytes().to_vec(),
+            WrappedValue::I8(value) => vec![value as u8],
+            WrappedValue::I16(value) => value.to_ne_bytes().to_vec(),
+            WrappedValue::I32(value) => value.to_ne_bytes().to_vec(),
+            WrappedValue::I64(value) => value.to_ne_bytes().to_vec(),
+            WrappedValue::I128(value) => value.to_ne_bytes().to_vec(),
+            WrappedValue::F32(value) => value.to_ne_bytes().to_vec(),
+            WrappedValue::F64(value) => value.to_ne_bytes().to_vec(),
+            WrappedValue::Fixed(value) if value.format.size() == 2 => {
+                (value.raw as i16).to_ne_bytes().to_vec()
+            }
+            WrappedValue::Fixed(value) => (value.raw as i32).to_ne_bytes().to_vec(),
+        }
+    }
+
+    /// Byte representation of packed values that have no native type.
+    fn packed_bytes(self, endianness: Endianness) -> Vec<u8> {
+        match (self, endianness) {
+            (WrappedValue::U16Packed(value), Endianness::Big) => value.to_be_bytes().to_vec(),
+            (WrappedValue::U16Packed(value), _) => value.to_le_bytes().to_vec(),
+            (WrappedValue::U24(value), Endianness::Big) => value.to_be_bytes()[1..].to_vec(),
+            (WrappedValue::U24(value), _) => value.to_le_bytes()[..3].to_vec(),
+            _ => vec![],
+        }
+    }
+
     pub fn scan_memory<'a>(
         self,
         buffer: &'a [u8],
@@ -188,16 +424,159 @@ impl WrappedValue {
         match self {
             WrappedValue::U8(needle) => Box::new(MemorySearch::new(needle, buffer)),
             WrappedValue::U16(needle) => Box::new(MemorySearch::new(needle, buffer)),
+            WrappedValue::U16Packed(_) | WrappedValue::U24(_) => {
+                Box::new(PackedSearch::new(&self.packed_bytes(endianness), buffer))
+            }
             WrappedValue::U32(needle) => Box::new(MemorySearch::new(needle, buffer)),
             WrappedValue::U64(needle) => Box::new(MemorySearch::new(needle, buffer)),
-            // WrappedValue::S8(needle) => Box::new(MemorySearch::new(needle, buffer)),
-            // WrappedValue::S16(needle) => Box::new(MemorySearch::new(needle, buffer)),
-            // WrappedValue::S32(needle) => Box::new(MemorySearch::new(needle, buffer)),
-            // WrappedValue::S64(needle) => Box::new(MemorySearch::new(needle, buffer)),
+            WrappedValue::U128(needle) => Box::new(MemorySearch::new(needle, buffer)),
+            WrappedValue::I8(needle) => Box::new(MemorySearch::new(needle, buffer)),
+            WrappedValue::I16(needle) => Box::new(MemorySearch::new(needle, buffer)),
+            WrappedValue::I32(needle) => Box::new(MemorySearch::new(needle, buffer)),
+            WrappedValue::I64(needle) => Box::new(MemorySearch::new(needle, buffer)),
+            WrappedValue::I128(needle) => Box::new(MemorySearch::new(needle, buffer)),
             WrappedValue::F32(needle) => Box::new(MemorySearch::new(needle, buffer)),
             WrappedValue::F64(needle) => Box::new(MemorySearch::new(needle, buffer)),
+            WrappedValue::Fixed(value) => value.scan_memory(buffer),
+        }
+    }
+
+    /// The bytes and alignment `scan_memory` matches, so a remote agent can
+    /// run the scan. `None` if matches aren't a single byte pattern, like
+    /// fixed point ranges or floats equal to zero, which match both signs.
+    pub fn search_pattern(self, endianness: Endianness) -> Option<(Vec<u8>, usize)> {
+        match self {
+            WrappedValue::Fixed(_) => None,
+            WrappedValue::F32(value) if value == 0.0 || value.is_nan() => None,
+            WrappedValue::F64(value) if value == 0.0 || value.is_nan() => None,
+            WrappedValue::U16Packed(_) | WrappedValue::U24(_) => {
+                Some((self.packed_bytes(endianness), 1))
+            }
+            _ => Some((self.to_ne_bytes(), self.data_type().size())),
+        }
+    }
+}
+
+macro_rules! collect_variant {
+    ($values:expr, $variant:ident) => {
+        $values
+            .iter()
+            .filter_map(|value| match value {
+                WrappedValue::$variant(value) => Some(*value),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+}
+
+impl WrappedValue {
+    pub fn scan_relative<'a>(
+        relation: Relation,
+        values: &[WrappedValue],
+        buffer: &'a [u8],
+        endianness: Endianness,
+    ) -> Result<Box<dyn Iterator<Item = usize> + 'a>, AppError> {
+        let Some(first) = values.first() else {
+            return Ok(Box::new(std::iter::empty()));
+        };
+        let swap = endianness.swaps();
+        match first.data_type() {
+            DataType::U8 => Ok(Box::new(RelativeSearch::new(
+                relation,
+                collect_variant!(values, U8),
+                buffer,
+            )
+            .swap_bytes(swap))),
+            DataType::U16 => Ok(Box::new(RelativeSearch::new(
+                relation,
+                collect_variant!(values, U16),
+                buffer,
+            )
+            .swap_bytes(swap))),
+            DataType::U32 => Ok(Box::new(RelativeSearch::new(
+                relation,
+                collect_variant!(values, U32),
+                buffer,
+            )
+            .swap_bytes(swap))),
+            DataType::U64 => Ok(Box::new(RelativeSearch::new(
+                relation,
+                collect_variant!(values, U64),
+                buffer,
+            )
+            .swap_bytes(swap))),
+            DataType::I8 => Ok(Box::new(RelativeSearch::new(
+                relation,
+                collect_variant!(values, I8),
+                buffer,
+            )
+            .swap_bytes(swap))),
+            DataType::I16 => Ok(Box::new(RelativeSearch::new(
+                relation,
+                collect_variant!(values, I16),
+                buffer,
+            )
+            .swap_bytes(swap))),
+            DataType::I32 => Ok(Box::new(RelativeSearch::new(
+                relation,
+                collect_variant!(values, I32),
+                buffer,
+            )
+            .swap_bytes(swap))),
+            DataType::I64 => Ok(Box::new(RelativeSearch::new(
+                relation,
+                collect_variant!(values, I64),
+                buffer,
+            )
+            .swap_bytes(swap))),
+            data_type => Err(relative_unsupported(data_type)),
         }
     }
+
+    pub fn changed_by_same_delta(
+        relation: Relation,
+        previous: WrappedValue,
+        last: WrappedValue,
+        current: WrappedValue,
+    ) -> bool {
+        match (previous, last, current) {
+            (WrappedValue::U8(a), WrappedValue::U8(b), WrappedValue::U8(c)) => {
+                relative::changed_by_same_delta(relation, a, b, c)
+            }
+            (WrappedValue::U16(a), WrappedValue::U16(b), WrappedValue::U16(c)) => {
+                relative::changed_by_same_delta(relation, a, b, c)
+            }
+            (WrappedValue::U32(a), WrappedValue::U32(b), WrappedValue::U32(c)) => {
+                relative::changed_by_same_delta(relation, a, b, c)
+            }
+            (WrappedValue::U64(a), WrappedValue::U64(b), WrappedValue::U64(c)) => {
+                relative::changed_by_same_delta(relation, a, b, c)
+            }
+            (WrappedValue::I8(a), WrappedValue::I8(b), WrappedValue::I8(c)) => {
+                relative::changed_by_same_delta(relation, a, b, c)
+            }
+            (WrappedValue::I16(a), WrappedValue::I16(b), WrappedValue::I16(c)) => {
+                relative::changed_by_same_delta(relation, a, b, c)
+            }
+            (WrappedValue::I32(a), WrappedValue::I32(b), WrappedValue::I32(c)) => {
+                relative::changed_by_same_delta(relation, a, b, c)
+            }
+            (WrappedValue::I64(a), WrappedValue::I64(b), WrappedValue::I64(c)) => {
+                relative::changed_by_same_delta(relation, a, b, c)
+            }
+            _ => false,
+        }
+    }
+}
+
+pub fn relation_picker(relation: &mut Relation, ui: &mut egui::Ui) {
+    ComboBox::from_label("Encoding")
+        .selected_text(format!("{:?}", relation))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(relation, Relation::Offset, "value + k");
+            ui.selectable_value(relation, Relation::Scale, "value * k");
+            ui.selectable_value(relation, Relation::Xor, "value ^ k");
+        });
 }
 
 impl Display for WrappedValue {
@@ -205,14 +584,57 @@ impl Display for WrappedValue {
         match self {
             WrappedValue::U8(value) => write!(f, "{}", value),
             WrappedValue::U16(value) => write!(f, "{}", value),
+            WrappedValue::U16Packed(value) => write!(f, "{}", value),
+            WrappedValue::U24(value) => write!(f, "{}", value),
             WrappedValue::U32(value) => write!(f, "{}", value),
             WrappedValue::U64(value) => write!(f, "{}", value),
-            // WrappedValue::S8(value) => write!(f, "{}", value),
-            // WrappedValue::S16(value) => write!(f, "{}", value),
-            // WrappedValue::S32(value) => write!(f, "{}", value),
-            // WrappedValue::S64(value) => write!(f, "{}", value),
+            WrappedValue::U128(value) => write!(f, "{}", value),
+            WrappedValue::I8(value) => write!(f, "{}", value),
+            WrappedValue::I16(value) => write!(f, "{}", value),
+            WrappedValue::I32(value) => write!(f, "{}", value),
+            WrappedValue::I64(value) => write!(f, "{}", value),
+            WrappedValue::I128(value) => write!(f, "{}", value),
             WrappedValue::F32(value) => write!(f, "{}", value),
             WrappedValue::F64(value) => write!(f, "{}", value),
+            WrappedValue::Fixed(value) => write!(f, "{}", value),
         }
     }
 }
+
+#[test]
+fn test_cast_endianness() {
+    let bytes = [0x12, 0x34, 0x56, 0x78];
+    let cast = |data_type: DataType, endianness| {
+        data_type
+            .cast(&bytes, endianness)
+            .unwrap_or_else(|err| panic!("{}", err))
+            .to_string()
+    };
+    assert_eq!(cast(DataType::U16Packed, Endianness::Little), 0x3412.to_string());
+    assert_eq!(cast(DataType::U16Packed, Endianness::Big), 0x1234.to_string());
+    assert_eq!(cast(DataType::U24, Endianness::Little), 0x563412.to_string());
+    assert_eq!(cast(DataType::U24, Endianness::Big), 0x123456.to_string());
+    assert_eq!(cast(DataType::U32, Endianness::Big), 0x12345678.to_string());
+    assert_eq!(cast(DataType::U16, Endianness::Little), 0x3412.to_string());
+    assert_eq!(cast(DataType::I8, Endianness::Big), 0x12.to_string());
+}
+
+#[test]
+fn test_write_fixed() {
+    let data_type = DataType::Fixed(FixedPoint::Q8_8);
+    let value = data_type
+        .parse("12.25")
+        .unwrap_or_else(|err| panic!("{}", err));
+    let bytes = value.to_ne_bytes();
+    assert_eq!(bytes, 3136i16.to_ne_bytes());
+    let read = data_type
+        .cast(&bytes, Endianness::Native)
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(read.to_string(), "12.25");
+
+    /* Values between two raw steps are written as the nearest one */
+    let value = DataType::Fixed(FixedPoint::Scaled(10))
+        .parse("-0.125")
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(value.to_ne_bytes(), (-1i32).to_ne_bytes());
+}
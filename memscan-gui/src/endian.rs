@@ -5,3 +5,15 @@ pub enum Endianness {
     #[default]
     Native,
 }
+
+impl Endianness {
+    /// Whether values in this byte order have to be reversed to read them
+    /// as native integers.
+    pub fn swaps(self) -> bool {
+        match self {
+            Endianness::Little => cfg!(target_endian = "big"),
+            Endianness::Big => cfg!(target_endian = "little"),
+            Endianness::Native => false,
+        }
+    }
+}
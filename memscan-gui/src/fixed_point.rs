@@ -0,0 +1,218 @@
+use std::fmt::Display;
+
+use memscan::search::{InclusiveRangeSearch, MemorySearch};
+
+use crate::{app_error::AppError, endian::Endianness};
+
+/// Fixed point formats named `Q<integer bits>_<fraction bits>`, prefixed with
+/// `U` when unsigned, and integers scaled by a constant factor.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, serde::Deserialize, serde::Serialize)]
+pub enum FixedPoint {
+    Q8_8,
+    UQ8_8,
+    Q16_16,
+    UQ16_16,
+    Q24_8,
+    UQ24_8,
+    /// Signed 32 bit integer holding `value * factor`, e.g. money in cents
+    Scaled(u32),
+}
+
+impl Display for FixedPoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FixedPoint::Q8_8 => write!(f, "Q8.8"),
+            FixedPoint::UQ8_8 => write!(f, "UQ8.8"),
+            FixedPoint::Q16_16 => write!(f, "Q16.16"),
+            FixedPoint::UQ16_16 => write!(f, "UQ16.16"),
+            FixedPoint::Q24_8 => write!(f, "Q24.8"),
+            FixedPoint::UQ24_8 => write!(f, "UQ24.8"),
+            FixedPoint::Scaled(factor) => write!(f, "Scaled x{}", factor),
+        }
+    }
+}
+
+impl FixedPoint {
+    pub fn size(self) -> usize {
+        match self {
+            FixedPoint::Q8_8 | FixedPoint::UQ8_8 => 2,
+            _ => 4,
+        }
+    }
+
+    fn scale(self) -> f64 {
+        match self {
+            FixedPoint::Q8_8 | FixedPoint::UQ8_8 | FixedPoint::Q24_8 | FixedPoint::UQ24_8 => 256.0,
+            FixedPoint::Q16_16 | FixedPoint::UQ16_16 => 65536.0,
+            FixedPoint::Scaled(factor) => factor.max(1) as f64,
+        }
+    }
+
+    /// Smallest and largest raw integer of the storage type.
+    fn raw_limits(self) -> (i64, i64) {
+        match self {
+            FixedPoint::Q8_8 => (i16::MIN as i64, i16::MAX as i64),
+            FixedPoint::UQ8_8 => (0, u16::MAX as i64),
+            FixedPoint::UQ16_16 | FixedPoint::UQ24_8 => (0, u32::MAX as i64),
+            FixedPoint::Q16_16 | FixedPoint::Q24_8 | FixedPoint::Scaled(_) => {
+                (i32::MIN as i64, i32::MAX as i64)
+            }
+        }
+    }
+
+    pub fn decode(self, raw: i64) -> f64 {
+        raw as f64 / self.scale()
+    }
+
+    /// Returns the range of raw values that round to the decimal in `label`.
+    /// The number of typed decimals sets the precision, so `12` matches
+    /// anything displayed as 12 while `12.25` only matches 12.25.
+    pub fn parse(self, label: &str) -> Result<FixedValue, AppError> {
+        let value: f64 = label.parse()?;
+        let decimals = label
+            .split_once('.')
+            .map_or(0, |(_, fraction)| fraction.len());
+        let half_step = 0.5 / 10f64.powi(decimals as i32);
+        let scale = self.scale();
+
+        let exact = (value * scale).round() as i64;
+        let lower = (((value - half_step) * scale).ceil() as i64).min(exact);
+        let upper = ((((value + half_step) * scale).ceil() as i64) - 1).max(exact);
+
+        let (min, max) = self.raw_limits();
+        if upper < min || lower > max {
+            return Err(AppError::DataTypeParseError(format!(
+                "{} is out of range for {}",
+                label, self
+            )));
+        }
+
+        Ok(FixedValue {
+            format: self,
+            raw: exact.clamp(min, max),
+            lower: lower.max(min),
+            upper: upper.min(max),
+        })
+    }
+
+    pub fn read(self, buffer: &[u8], endianness: Endianness) -> i64 {
+        let mut bytes = [0u8; 4];
+        let bytes = &mut bytes[..self.size()];
+        bytes.copy_from_slice(&buffer[..bytes.len()]);
+        if endianness.swaps() {
+            bytes.reverse();
+        }
+        match self {
+            FixedPoint::Q8_8 => i16::from_ne_bytes([bytes[0], bytes[1]]) as i64,
+            FixedPoint::UQ8_8 => u16::from_ne_bytes([bytes[0], bytes[1]]) as i64,
+            FixedPoint::UQ16_16 | FixedPoint::UQ24_8 => {
+                u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as i64
+            }
+            FixedPoint::Q16_16 | FixedPoint::Q24_8 | FixedPoint::Scaled(_) => {
+                i32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as i64
+            }
+        }
+    }
+}
+
+/// A fixed point value, or the range of raw values matching a search input.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedValue {
+    pub format: FixedPoint,
+    /// Raw integer closest to the value, this is what gets written
+    pub raw: i64,
+    pub lower: i64,
+    pub upper: i64,
+}
+
+macro_rules! scan_raw {
+    ($ty:ty, $value:expr, $buffer:expr) => {
+        if $value.lower == $value.upper {
+            Box::new(MemorySearch::new($value.lower as $ty, $buffer))
+        } else {
+            Box::new(InclusiveRangeSearch::new(
+                $value.lower as $ty,
+                $value.upper as $ty,
+                $buffer,
+            ))
+        }
+    };
+}
+
+impl FixedValue {
+    pub fn contains(self, raw: i64) -> bool {
+        self.lower <= raw && raw <= self.upper
+    }
+
+    /// Searches the raw integers with the regular integer search.
+    pub fn scan_memory<'a>(self, buffer: &'a [u8]) -> Box<dyn Iterator<Item = usize> + 'a> {
+        match self.format {
+            FixedPoint::Q8_8 => scan_raw!(i16, self, buffer),
+            FixedPoint::UQ8_8 => scan_raw!(u16, self, buffer),
+            FixedPoint::UQ16_16 | FixedPoint::UQ24_8 => scan_raw!(u32, self, buffer),
+            FixedPoint::Q16_16 | FixedPoint::Q24_8 | FixedPoint::Scaled(_) => {
+                scan_raw!(i32, self, buffer)
+            }
+        }
+    }
+}
+
+impl Display for FixedValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.lower == self.upper {
+            write!(f, "{}", self.format.decode(self.lower))
+        } else {
+            write!(
+                f,
+                "{}..={}",
+                self.format.decode(self.lower),
+                self.format.decode(self.upper)
+            )
+        }
+    }
+}
+
+#[test]
+fn test_fixed_point_parse() {
+    let value = FixedPoint::Q8_8
+        .parse("12")
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!((value.lower, value.upper), (2944, 3199));
+
+    /* Every typed decimal narrows the range */
+    let value = FixedPoint::Q8_8
+        .parse("12.25")
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!((value.lower, value.upper), (3135, 3137));
+    assert_eq!(value.raw, 3136);
+    assert!(value.contains(12 * 256 + 64));
+
+    let value = FixedPoint::Q8_8
+        .parse("-1")
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert!(value.contains(-256) && !value.contains(-385) && !value.contains(-128));
+
+    let value = FixedPoint::Scaled(100)
+        .parse("1.5")
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert!(value.contains(145) && value.contains(150));
+    assert!(!value.contains(144) && !value.contains(156));
+
+    assert!(FixedPoint::UQ8_8.parse("-5").is_err());
+    assert!(FixedPoint::Q8_8.parse("200").is_err());
+    assert!(FixedPoint::Q8_8.parse("1.x").is_err());
+
+    /* Ranges reaching past the storage type are clamped */
+    let value = FixedPoint::UQ8_8
+        .parse("0")
+        .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(value.lower, 0);
+}
+
+#[test]
+fn test_fixed_point_read() {
+    let raw = FixedPoint::Q8_8.read(&[0x01, 0x80], Endianness::Big);
+    assert_eq!(FixedPoint::Q8_8.decode(raw), 1.5);
+    let raw = FixedPoint::Q16_16.read(&[0, 0, 0xff, 0xff], Endianness::Little);
+    assert_eq!(FixedPoint::Q16_16.decode(raw), -1.0);
+}
@@ -1,11 +1,23 @@
+mod address_expr;
 mod app;
 mod app_error;
+mod change_scan;
+mod cheat_table;
 mod data_types;
 mod endian;
+mod fixed_point;
+mod module_address;
+mod pointer_scan;
+mod process_memory;
 mod process_picker;
+mod process_stop;
+mod region_filter;
+mod scan_history;
 mod search;
 mod search_scope;
+mod session;
 mod settings;
+mod table_import;
 
 use std::process::Command;
 
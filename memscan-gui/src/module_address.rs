@@ -0,0 +1,67 @@
+use std::{fmt::Display, path::Path};
+
+use memscan::memory_source::MemoryRegion;
+
+use crate::{app_error::AppError, process_memory};
+
+/// An address given as an offset into a mapped file, which stays valid when
+/// the file is mapped at a different address after a restart.
+///
+/// It is shown as `libgame.so@0x1234`. Address expressions and pointer
+/// chains write `libgame.so+0x1234` for an offset from the lowest address the
+/// module is mapped at, which differs from the file offset after the first
+/// segment.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub struct ModuleAddress {
+    pub path: String,
+    /// Offset into the file, as in the `offset` column of `/proc/<pid>/maps`
+    pub offset: u64,
+}
+
+impl Display for ModuleAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = Path::new(&self.path)
+            .file_name()
+            .map_or(self.path.as_str(), |name| name.to_str().unwrap_or_default());
+        write!(f, "{}@{:#x}", name, self.offset)
+    }
+}
+
+/// Path of the file behind the mapping, if it maps one rather than being
+/// anonymous or a pseudo mapping like `[heap]`.
+pub fn map_path(map: &MemoryRegion) -> Option<String> {
+    map.pathname
+        .clone()
+        .filter(|path| !path.is_empty() && !path.starts_with('['))
+}
+
+impl ModuleAddress {
+    pub fn new(path: String, map_start: u64, map_offset: u64, address: u64) -> Self {
+        Self {
+            path,
+            offset: map_offset + (address - map_start),
+        }
+    }
+
+    /// Expresses `address` relative to the file mapped there, if any.
+    pub fn from_maps(maps: &[MemoryRegion], address: u64) -> Option<Self> {
+        let map = maps.iter().find(|map| map.contains(address))?;
+        Some(Self::new(map_path(map)?, map.start, map.offset, address))
+    }
+
+    pub fn from_process(pid: i32, address: u64) -> Result<Option<Self>, AppError> {
+        Ok(Self::from_maps(&process_memory::regions(pid)?, address))
+    }
+
+    /// Finds where the file offset is mapped now.
+    pub fn resolve(&self, maps: &[MemoryRegion]) -> Option<u64> {
+        maps.iter()
+            .filter(|map| map_path(map).as_deref() == Some(self.path.as_str()))
+            .find(|map| map.offset <= self.offset && self.offset - map.offset < map.end - map.start)
+            .map(|map| map.start + (self.offset - map.offset))
+    }
+
+    pub fn resolve_in(&self, pid: i32) -> Result<Option<u64>, AppError> {
+        Ok(self.resolve(&process_memory::regions(pid)?))
+    }
+}
@@ -0,0 +1,226 @@
+use std::path::Path;
+
+use egui::ScrollArea;
+use memscan::{
+    pointer_map_file::{SavedPointerMap, intersect_chains},
+    pointer_scan::{MappedRegion, PointerChain, PointerMap, PointerScan},
+};
+
+use crate::{
+    app_error::AppError,
+    module_address::map_path,
+    process_memory,
+    process_stop::StoppedProcesses,
+    search::{ScanCoverage, read_chunk},
+    settings::Settings,
+};
+
+pub struct PointerScanner {
+    show_scanner: bool,
+    pid: i32,
+    target: String,
+    max_offset: u64,
+    max_depth: usize,
+    chains: Vec<PointerChain>,
+    /// Memory read for the last scan
+    coverage: Option<ScanCoverage>,
+    /// Map and target of the last scan, kept for saving
+    last_scan: Option<SavedPointerMap>,
+    map_path: String,
+    /// Maps saved in other runs that the chains are compared against
+    compare_paths: Vec<String>,
+}
+
+impl Default for PointerScanner {
+    fn default() -> Self {
+        Self {
+            show_scanner: false,
+            pid: 0,
+            target: String::new(),
+            max_offset: 0x1000,
+            max_depth: 4,
+            chains: vec![],
+            coverage: None,
+            last_scan: None,
+            map_path: String::from("pointers.mspm"),
+            compare_paths: vec![],
+        }
+    }
+}
+
+/// Lists the readable regions of a process. Anonymous mappings directly
+/// following a module are attributed to it, as that is where its `.bss` lives.
+pub fn load_mapped_regions(pid: i32) -> Result<Vec<MappedRegion>, AppError> {
+    let mut regions: Vec<MappedRegion> = vec![];
+    for map in process_memory::regions(pid)? {
+        if !map.readable {
+            continue;
+        }
+        let module = match map_path(&map) {
+            Some(path) => Path::new(&path)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned()),
+            None if map.pathname.is_none() => regions
+                .last()
+                .filter(|previous| previous.end == map.start)
+                .and_then(|previous| previous.module.clone()),
+            None => None,
+        };
+        regions.push(MappedRegion {
+            start: map.start,
+            end: map.end,
+            module,
+        });
+    }
+    Ok(regions)
+}
+
+/// Reads every readable region of the process and collects its pointers.
+pub fn build_pointer_map(
+    pid: i32,
+    settings: &Settings,
+    coverage: &mut ScanCoverage,
+) -> Result<PointerMap, AppError> {
+    let mut map = PointerMap::new(load_mapped_regions(pid)?);
+
+    let step_size = settings.search_buffer_size;
+    let mut read_buffer = vec![0u8; step_size];
+    let regions = map.regions().to_vec();
+    let source = process_memory::source(pid);
+    for region in regions {
+        for chunk_start in (region.start..region.end).step_by(step_size) {
+            let chunk_end = (chunk_start + step_size as u64).min(region.end);
+            let chunk_length = (chunk_end - chunk_start) as usize;
+            let buffer = &mut read_buffer[..chunk_length];
+            for part in read_chunk(&*source, pid, chunk_start, buffer, coverage)? {
+                map.add_memory(chunk_start + part.start as u64, &buffer[part]);
+            }
+        }
+    }
+    map.finish();
+
+    Ok(map)
+}
+
+impl PointerScanner {
+    pub fn toggle(&mut self) {
+        self.show_scanner = !self.show_scanner;
+    }
+
+    pub fn open(&mut self, pid: i32, target: u64) {
+        self.pid = pid;
+        self.target = format!("{:x}", target);
+        self.show_scanner = true;
+    }
+
+    fn scan(&mut self, settings: &Settings) -> Result<(), AppError> {
+        let target = u64::from_str_radix(self.target.trim().trim_start_matches("0x"), 16)?;
+        let mut coverage = ScanCoverage::default();
+        let map = {
+            /* Resumed once the memory is read, the chains are searched in the map */
+            let _stopped = settings
+                .stop_processes
+                .then(|| StoppedProcesses::stop([self.pid]))
+                .transpose()?;
+            build_pointer_map(self.pid, settings, &mut coverage)?
+        };
+        self.coverage = Some(coverage);
+        self.chains = PointerScan::new(&map, self.max_offset, self.max_depth).scan(target);
+        self.last_scan = Some(SavedPointerMap {
+            target: Some(target),
+            map,
+        });
+        Ok(())
+    }
+
+    fn save_map(&self) -> Result<(), AppError> {
+        let Some(saved) = &self.last_scan else {
+            return Err(AppError::Io("No pointer map, scan first".to_string()));
+        };
+        saved.save(self.map_path.trim())?;
+        Ok(())
+    }
+
+    /// Drops the chains that do not reach the target in every compared map.
+    fn compare_maps(&mut self) -> Result<(), AppError> {
+        let maps = self
+            .compare_paths
+            .iter()
+            .map(|path| SavedPointerMap::load(path.trim()))
+            .collect::<Result<Vec<_>, _>>()?;
+        intersect_chains(&mut self.chains, &maps);
+        Ok(())
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, settings: &Settings, error: &mut Option<AppError>) {
+        let mut show_scanner = self.show_scanner;
+        egui::Window::new("Pointer Scan")
+            .resizable(true)
+            .collapsible(true)
+            .open(&mut show_scanner)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("PID:");
+                    ui.add(egui::DragValue::new(&mut self.pid));
+                    ui.label("Target:");
+                    ui.text_edit_singleline(&mut self.target);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Max offset:");
+                    ui.add(egui::DragValue::new(&mut self.max_offset).hexadecimal(4, false, true));
+                    ui.label("Max depth:");
+                    ui.add(egui::DragValue::new(&mut self.max_depth).range(1..=8));
+                });
+                ui.button("Scan").clicked().then(|| {
+                    if let Err(err) = self.scan(settings) {
+                        eprintln!("Error: {err}");
+                        *error = Some(err);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Map file:");
+                    ui.text_edit_singleline(&mut self.map_path);
+                    ui.button("Save map").clicked().then(|| {
+                        if let Err(err) = self.save_map() {
+                            eprintln!("Error: {err}");
+                            *error = Some(err);
+                        }
+                    });
+                });
+                ui.collapsing("Compare pointer maps", |ui| {
+                    let mut removed = None;
+                    for (index, path) in self.compare_paths.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            ui.text_edit_singleline(path);
+                            ui.button("Remove").clicked().then(|| removed = Some(index));
+                        });
+                    }
+                    if let Some(index) = removed {
+                        self.compare_paths.remove(index);
+                    }
+                    ui.horizontal(|ui| {
+                        ui.button("Add map").clicked().then(|| {
+                            self.compare_paths.push(self.map_path.clone());
+                        });
+                        ui.button("Compare").clicked().then(|| {
+                            if let Err(err) = self.compare_maps() {
+                                eprintln!("Error: {err}");
+                                *error = Some(err);
+                            }
+                        });
+                    });
+                });
+                ui.separator();
+                if let Some(coverage) = &self.coverage {
+                    ui.label(coverage.summary());
+                }
+                ui.label(format!("Chains: {}", self.chains.len()));
+                ScrollArea::vertical().show(ui, |ui| {
+                    for chain in &self.chains {
+                        ui.monospace(chain.to_string());
+                    }
+                });
+            });
+        self.show_scanner = show_scanner;
+    }
+}
@@ -0,0 +1,177 @@
+use std::{
+    io,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use memscan::{
+    address_set::AddressSet,
+    core_dump::CoreDump,
+    gdb_remote::GdbRemote,
+    memory_source::{MemoryRegion, MemorySource, ProcessVmSource},
+    remote::{RemoteConnection, RemoteSource},
+    snapshot::Snapshot,
+};
+
+use crate::{app_error::AppError, process_picker::Process, region_filter::RegionMatcher};
+
+type SharedSources = Vec<Arc<Mutex<dyn MemorySource + Send>>>;
+
+/// Opened core dumps, snapshots and remote processes, source `n` is scanned
+/// under the pseudo pid `-n - 1`.
+static SOURCES: Mutex<SharedSources> = Mutex::new(Vec::new());
+
+/// Access to a registered source.
+struct SharedSource(Arc<Mutex<dyn MemorySource + Send>>);
+
+impl MemorySource for SharedSource {
+    fn regions(&self) -> io::Result<Vec<MemoryRegion>> {
+        self.0.lock().unwrap().regions()
+    }
+
+    fn read(&self, address: u64, buffer: &mut [u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().read(address, buffer)
+    }
+
+    fn write(&mut self, address: u64, data: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(address, data)
+    }
+
+    fn is_remote(&self) -> bool {
+        self.0.lock().unwrap().is_remote()
+    }
+
+    fn find_bytes(
+        &self,
+        start: u64,
+        end: u64,
+        needle: &[u8],
+        alignment: usize,
+    ) -> io::Result<AddressSet> {
+        self.0
+            .lock()
+            .unwrap()
+            .find_bytes(start, end, needle, alignment)
+    }
+}
+
+/// The memory of the process with `pid`, every read and write of the scanner
+/// goes through this. Negative pids are core dumps, snapshots and remote
+/// processes opened with `open_core_dump`, `open_snapshot`, `connect_agent`
+/// and `connect_gdb`.
+pub fn source(pid: i32) -> Box<dyn MemorySource> {
+    if pid < 0 {
+        let sources = SOURCES.lock().unwrap();
+        if let Some(source) = sources.get((-pid - 1) as usize) {
+            return Box::new(SharedSource(source.clone()));
+        }
+    }
+    Box::new(ProcessVmSource::new(pid))
+}
+
+/// The mapped regions of `pid`. Sources that can't list them, like gdb
+/// stubs without a memory map, fail with a `RegionFilterError`.
+pub fn regions(pid: i32) -> Result<Vec<MemoryRegion>, AppError> {
+    source(pid).regions().map_err(|err| match err.kind() {
+        io::ErrorKind::NotFound => AppError::ProcessNotFound,
+        io::ErrorKind::Unsupported => AppError::RegionFilterError(err.to_string()),
+        _ => AppError::from(err),
+    })
+}
+
+/// The regions of `pid` that `matcher` keeps. Sources that can't list their
+/// regions are read in the address ranges of the filter as they are.
+pub fn selected_regions(pid: i32, matcher: &RegionMatcher) -> Result<Vec<MemoryRegion>, AppError> {
+    match regions(pid) {
+        Ok(maps) => Ok(matcher.select(pid, maps)),
+        Err(AppError::RegionFilterError(message)) => matcher
+            .address_regions()
+            .ok_or(AppError::RegionFilterError(message)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Registers a source, returning the selected pseudo process to add to the
+/// process list.
+fn add_source(source: Arc<Mutex<dyn MemorySource + Send>>, cmd: String, uid: u32) -> Process {
+    let mut sources = SOURCES.lock().unwrap();
+    sources.push(source);
+    Process {
+        pid: -(sources.len() as i32),
+        uid,
+        cmd,
+        selected: true,
+    }
+}
+
+/// Opens a core dump so it can be searched like a process.
+pub fn open_core_dump(path: impl AsRef<Path>) -> Result<Process, AppError> {
+    let dump = CoreDump::open(path)?;
+    let cmd = format!("{} (core of {})", dump.command(), dump.pid());
+    Ok(add_source(Arc::new(Mutex::new(dump)), cmd, 0))
+}
+
+/// Opens a snapshot taken with `dump_process` so it can be searched like a
+/// process.
+pub fn open_snapshot(path: impl AsRef<Path>) -> Result<Process, AppError> {
+    let snapshot = Snapshot::load(path)?;
+    let cmd = format!("{} (snapshot of {})", snapshot.command, snapshot.pid);
+    Ok(add_source(Arc::new(Mutex::new(snapshot)), cmd, 0))
+}
+
+/// Connects to a `memscan-agent` at `<token>@<host:port>` or
+/// `<token>@unix:<path>` and returns its processes, which are searched on the
+/// agent's machine. Without a token the one in `MEMSCAN_AGENT_TOKEN` is sent.
+pub fn connect_agent(address: &str) -> Result<Vec<Process>, AppError> {
+    let (token, address) = match address.split_once('@') {
+        Some((token, address)) => (token.to_string(), address),
+        None => (
+            std::env::var("MEMSCAN_AGENT_TOKEN").unwrap_or_default(),
+            address,
+        ),
+    };
+    let connection = Arc::new(RemoteConnection::connect(address, &token)?);
+    Ok(connection
+        .processes()?
+        .into_iter()
+        .map(|process| {
+            let source = RemoteSource::new(connection.clone(), process.pid);
+            let cmd = format!("{} (remote {} on {})", process.cmd, process.pid, address);
+            /* Agents list every process, let the user pick */
+            Process {
+                selected: false,
+                ..add_source(Arc::new(Mutex::new(source)), cmd, process.uid)
+            }
+        })
+        .collect())
+}
+
+/// Connects to a gdb stub at `host:port` or `unix:<path>`, like `gdbserver`
+/// or an emulator, and returns its target.
+pub fn connect_gdb(address: &str) -> Result<Process, AppError> {
+    let remote = GdbRemote::connect(address)?;
+    let cmd = match remote.pid() {
+        Some(pid) => format!("gdb target {} on {}", pid, address),
+        None => format!("gdb target on {}", address),
+    };
+    Ok(add_source(Arc::new(Mutex::new(remote)), cmd, 0))
+}
+
+/// Writes the `regions` of `process` into a snapshot file, `settings`
+/// describes how the regions were chosen.
+pub fn dump_process(
+    path: impl AsRef<Path>,
+    process: &Process,
+    regions: Vec<MemoryRegion>,
+    settings: String,
+    compressed: bool,
+) -> Result<(), AppError> {
+    let snapshot = Snapshot::capture(
+        &*source(process.pid),
+        process.pid,
+        process.cmd.clone(),
+        settings,
+        regions,
+    )?;
+    Ok(snapshot.save(path, compressed)?)
+}
@@ -48,6 +48,15 @@ impl ProcessPicker {
         processes
     }
 
+    /// Adds a process that is not running, like an opened core dump.
+    pub fn add(&mut self, process: Process) {
+        self.processes.push(process);
+    }
+
+    pub fn processes(&self) -> &[Process] {
+        &self.processes
+    }
+
     pub fn selected_processes(&self) -> Vec<Process> {
         self.processes
             .iter()
@@ -58,7 +67,9 @@ impl ProcessPicker {
 
     pub fn show(&mut self, ui: &mut egui::Ui) {
         ui.button("Refresh").clicked().then(|| {
-            self.processes = Self::fetch_processes();
+            /* Dumps, snapshots and remote processes have negative pids and stay */
+            let dumps = self.processes.iter().filter(|prc| prc.pid < 0).cloned();
+            self.processes = Self::fetch_processes().into_iter().chain(dumps).collect();
         });
         ui.label("Running processes:");
         
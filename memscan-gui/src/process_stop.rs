@@ -0,0 +1,121 @@
+use std::fs;
+
+use nix::{
+    errno::Errno,
+    sys::{
+        ptrace::{self, Options},
+        signal::Signal,
+        wait::{WaitPidFlag, WaitStatus, waitpid},
+    },
+    unistd::{Pid, gettid},
+};
+
+use crate::app_error::AppError;
+
+/// Keeps every thread of some live processes stopped with ptrace, so a scan
+/// sees one consistent state of their memory.
+///
+/// Dropping it resumes the threads, also when the scan failed. If the scanner
+/// exits while they are stopped the kernel detaches and resumes them. Ptrace
+/// requests only work from the attaching thread, so stop, scan and resume on
+/// the same thread.
+#[derive(Default)]
+pub struct StoppedProcesses {
+    /// Stopped threads and the signal they were about to receive
+    threads: Vec<(Pid, Option<Signal>)>,
+}
+
+impl StoppedProcesses {
+    /// Stops the processes in `pids`. Pseudo processes like core dumps and
+    /// the scanner itself are skipped, as are processes this thread stopped
+    /// already, which stay stopped until the outer guard is dropped.
+    pub fn stop(pids: impl IntoIterator<Item = i32>) -> Result<Self, AppError> {
+        let own_pid = std::process::id() as i32;
+        let mut pids: Vec<i32> = pids
+            .into_iter()
+            .filter(|&pid| pid > 0 && pid != own_pid && !traced_by_this_thread(pid))
+            .collect();
+        pids.sort_unstable();
+        pids.dedup();
+
+        /* Threads stopped so far are resumed by the drop if one fails */
+        let mut stopped = Self::default();
+        for pid in pids {
+            stopped.stop_process(pid)?;
+        }
+        Ok(stopped)
+    }
+
+    fn stop_process(&mut self, pid: i32) -> Result<(), AppError> {
+        /* Running threads can start new ones, so repeat until none shows up */
+        loop {
+            let mut found_new = false;
+            for tid in fs::read_dir(format!("/proc/{}/task", pid))? {
+                let Ok(tid) = tid?.file_name().to_string_lossy().parse() else {
+                    continue;
+                };
+                let tid = Pid::from_raw(tid);
+                if self.threads.iter().any(|(other, _)| *other == tid) {
+                    continue;
+                }
+                found_new = true;
+                match ptrace::seize(tid, Options::empty()) {
+                    Ok(()) => {}
+                    /* The thread exited in the meantime */
+                    Err(Errno::ESRCH) => continue,
+                    Err(err) => return Err(err.into()),
+                }
+                /* Recorded right away, so the drop detaches it if stopping fails */
+                self.threads.push((tid, None));
+                ptrace::interrupt(tid)?;
+                match wait_for_stop(tid)? {
+                    Some(signal) => self.threads.last_mut().unwrap().1 = signal,
+                    None => {
+                        self.threads.pop();
+                    }
+                }
+            }
+            if !found_new {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Whether the main thread of `pid` is traced from the calling thread.
+fn traced_by_this_thread(pid: i32) -> bool {
+    let tracer = fs::read_to_string(format!("/proc/{}/status", pid))
+        .ok()
+        .and_then(|status| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix("TracerPid:"))
+                .and_then(|tracer| tracer.trim().parse::<i32>().ok())
+        });
+    tracer == Some(gettid().as_raw())
+}
+
+/// Waits until `tid` stopped, returning the signal it has to receive when
+/// it is resumed. `None` if the thread exited.
+fn wait_for_stop(tid: Pid) -> Result<Option<Option<Signal>>, AppError> {
+    match waitpid(tid, Some(WaitPidFlag::__WALL))? {
+        WaitStatus::PtraceEvent(..) => Ok(Some(None)),
+        /* A signal arrived before the interrupt, deliver it on detach */
+        WaitStatus::Stopped(_, signal) => Ok(Some(Some(signal))),
+        WaitStatus::Exited(..) | WaitStatus::Signaled(..) => Ok(None),
+        status => Err(AppError::Io(format!(
+            "Unexpected state of thread {}: {:?}",
+            tid, status
+        ))),
+    }
+}
+
+impl Drop for StoppedProcesses {
+    fn drop(&mut self) {
+        for (tid, signal) in self.threads.drain(..) {
+            if let Err(err) = ptrace::detach(tid, signal) {
+                println!("[ERROR]: Failed to resume thread {}: {}", tid, err);
+            }
+        }
+    }
+}
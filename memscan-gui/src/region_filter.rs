@@ -0,0 +1,510 @@
+use std::{fs, ops::Range};
+
+use egui::ComboBox;
+use memscan::memory_source::MemoryRegion;
+use regex::Regex;
+
+use crate::{app_error::AppError, module_address::map_path};
+
+/// Whether a permission bit has to be set, has to be cleared or is ignored.
+#[derive(Debug, PartialEq, Default, Clone, Copy, serde::Deserialize, serde::Serialize)]
+pub enum Flag {
+    #[default]
+    Any,
+    Set,
+    Cleared,
+}
+
+impl Flag {
+    fn matches(self, set: bool) -> bool {
+        match self {
+            Flag::Any => true,
+            Flag::Set => set,
+            Flag::Cleared => !set,
+        }
+    }
+
+    fn picker_for(&mut self, ui: &mut egui::Ui, label: &str, set: &str, cleared: &str) {
+        let text = match self {
+            Flag::Any => "Any",
+            Flag::Set => set,
+            Flag::Cleared => cleared,
+        };
+        ComboBox::from_label(label)
+            .selected_text(text)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(self, Flag::Any, "Any");
+                ui.selectable_value(self, Flag::Set, set);
+                ui.selectable_value(self, Flag::Cleared, cleared);
+            });
+    }
+}
+
+/// What a region maps.
+#[derive(Debug, PartialEq, Default, Clone, Copy, serde::Deserialize, serde::Serialize)]
+pub enum Backing {
+    #[default]
+    Any,
+    /// No file, including pseudo paths like `[heap]`
+    Anonymous,
+    File,
+}
+
+/// Selects the regions of a process to search by their mapping.
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct RegionFilter {
+    pub readable: Flag,
+    pub writable: Flag,
+    pub executable: Flag,
+    pub shared: Flag,
+    pub backing: Backing,
+    /// Regex the pathname has to match, empty for any
+    pub include: String,
+    /// Regex of pathnames to leave out, empty for none
+    pub exclude: String,
+    pub min_size: u64,
+    /// Largest region size in bytes, 0 for no limit
+    pub max_size: u64,
+    /// Hex ranges like `7f0000000000-7fffffffffff` separated by commas,
+    /// regions are cut to them. Empty for the whole address space.
+    pub address_ranges: String,
+    /// Only the stacks of the process' threads
+    pub thread_stacks: bool,
+}
+
+impl Default for RegionFilter {
+    fn default() -> Self {
+        Self {
+            readable: Flag::Set,
+            writable: Flag::Any,
+            executable: Flag::Any,
+            shared: Flag::Any,
+            backing: Backing::Any,
+            include: String::new(),
+            exclude: String::new(),
+            min_size: 0,
+            max_size: 0,
+            address_ranges: String::new(),
+            thread_stacks: false,
+        }
+    }
+}
+
+impl RegionFilter {
+    /// Regions whose pathname matches `include`.
+    pub fn pathname(include: &str) -> Self {
+        Self {
+            include: include.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn compile(&self) -> Result<RegionMatcher, AppError> {
+        let regex = |pattern: &str| {
+            (!pattern.is_empty())
+                .then(|| Regex::new(pattern))
+                .transpose()
+        };
+        let ranges = self
+            .address_ranges
+            .split(',')
+            .map(str::trim)
+            .filter(|range| !range.is_empty())
+            .map(parse_range)
+            .collect::<Result<_, _>>()?;
+        Ok(RegionMatcher {
+            filter: self.clone(),
+            include: regex(&self.include)?,
+            exclude: regex(&self.exclude)?,
+            ranges,
+        })
+    }
+
+    pub fn editor(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            self.readable
+                .picker_for(ui, "Read", "Readable", "Not readable");
+            self.writable
+                .picker_for(ui, "Write", "Writable", "Not writable");
+        });
+        ui.horizontal(|ui| {
+            self.executable
+                .picker_for(ui, "Execute", "Executable", "Not executable");
+            self.shared.picker_for(ui, "Sharing", "Shared", "Private");
+        });
+        ComboBox::from_label("Mapping")
+            .selected_text(format!("{:?}", self.backing))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.backing, Backing::Any, "Any");
+                ui.selectable_value(&mut self.backing, Backing::Anonymous, "Anonymous");
+                ui.selectable_value(&mut self.backing, Backing::File, "File");
+            });
+        ui.horizontal(|ui| {
+            ui.label("Include:");
+            ui.text_edit_singleline(&mut self.include)
+                .on_hover_text("Regex of pathnames to search, like libgame\\.so");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Exclude:");
+            ui.text_edit_singleline(&mut self.exclude)
+                .on_hover_text("Regex of pathnames to skip, like ^/usr/lib");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Size:");
+            ui.add(egui::DragValue::new(&mut self.min_size).hexadecimal(1, false, true));
+            ui.label("to");
+            ui.add(egui::DragValue::new(&mut self.max_size).hexadecimal(1, false, true))
+                .on_hover_text("0 for no limit");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Addresses:");
+            ui.text_edit_singleline(&mut self.address_ranges)
+                .on_hover_text("Hex ranges like 7f0000000000-7fffffffffff, separated by commas");
+        });
+        ui.checkbox(&mut self.thread_stacks, "Thread stacks only")
+            .on_hover_text("The main stack and every region a thread's stack pointer is in");
+    }
+}
+
+fn parse_range(range: &str) -> Result<Range<u64>, AppError> {
+    let invalid = || AppError::RegionFilterError(format!("Invalid address range {}", range));
+    let (start, end) = range.split_once('-').ok_or_else(invalid)?;
+    let parse = |address: &str| {
+        u64::from_str_radix(address.trim().trim_start_matches("0x"), 16).map_err(|_| invalid())
+    };
+    let (start, end) = (parse(start)?, parse(end)?);
+    if start >= end {
+        return Err(invalid());
+    }
+    Ok(start..end)
+}
+
+/// A `RegionFilter` with its regexes and address ranges parsed.
+pub struct RegionMatcher {
+    filter: RegionFilter,
+    include: Option<Regex>,
+    exclude: Option<Regex>,
+    ranges: Vec<Range<u64>>,
+}
+
+impl RegionMatcher {
+    /// The regions of process `pid` the filter keeps, cut to its address
+    /// ranges.
+    pub fn select(&self, pid: i32, maps: Vec<MemoryRegion>) -> Vec<MemoryRegion> {
+        let stack_pointers = if self.filter.thread_stacks {
+            thread_stack_pointers(pid)
+        } else {
+            vec![]
+        };
+        maps.into_iter()
+            .filter(|map| self.matches(map, &stack_pointers))
+            .flat_map(|map| self.clip(map))
+            .collect()
+    }
+
+    /// The address ranges as readable and writable regions, for sources
+    /// without a list of regions. `None` without ranges.
+    pub fn address_regions(&self) -> Option<Vec<MemoryRegion>> {
+        (!self.ranges.is_empty()).then(|| {
+            self.ranges
+                .iter()
+                .map(|range| MemoryRegion {
+                    start: range.start,
+                    end: range.end,
+                    readable: true,
+                    writable: true,
+                    executable: false,
+                    shared: false,
+                    pathname: None,
+                    offset: 0,
+                })
+                .collect()
+        })
+    }
+
+    fn matches(&self, map: &MemoryRegion, stack_pointers: &[u64]) -> bool {
+        let filter = &self.filter;
+        let pathname = map.pathname.as_deref().unwrap_or_default();
+        let size = map.end - map.start;
+        let backing = match filter.backing {
+            Backing::Any => true,
+            Backing::Anonymous => map_path(map).is_none(),
+            Backing::File => map_path(map).is_some(),
+        };
+        let is_stack = pathname == "[stack]"
+            || pathname.starts_with("[stack:")
+            || stack_pointers.iter().any(|&pointer| map.contains(pointer));
+        filter.readable.matches(map.readable)
+            && filter.writable.matches(map.writable)
+            && filter.executable.matches(map.executable)
+            && filter.shared.matches(map.shared)
+            && backing
+            && self
+                .include
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(pathname))
+            && !self
+                .exclude
+                .as_ref()
+                .is_some_and(|regex| regex.is_match(pathname))
+            && size >= filter.min_size
+            && (filter.max_size == 0 || size <= filter.max_size)
+            && (!filter.thread_stacks || is_stack)
+    }
+
+    fn clip(&self, map: MemoryRegion) -> Vec<MemoryRegion> {
+        if self.ranges.is_empty() {
+            return vec![map];
+        }
+        self.ranges
+            .iter()
+            .filter_map(|range| {
+                let start = range.start.max(map.start);
+                let end = range.end.min(map.end);
+                (start < end).then(|| MemoryRegion {
+                    start,
+                    end,
+                    offset: map.offset + (start - map.start),
+                    ..map.clone()
+                })
+            })
+            .collect()
+    }
+}
+
+/// Stack pointers of the threads of `pid` that are blocked, from
+/// `/proc/<pid>/task/<tid>/syscall`. Running threads and processes that
+/// can't be traced have none.
+fn thread_stack_pointers(pid: i32) -> Vec<u64> {
+    let Ok(tasks) = fs::read_dir(format!("/proc/{}/task", pid)) else {
+        return vec![];
+    };
+    tasks
+        .filter_map(|task| {
+            let syscall = fs::read_to_string(task.ok()?.path().join("syscall")).ok()?;
+            /* Ends with the stack pointer and the program counter */
+            let fields: Vec<&str> = syscall.split_whitespace().collect();
+            let pointer = fields.len().checked_sub(2).map(|index| fields[index])?;
+            u64::from_str_radix(pointer.strip_prefix("0x")?, 16).ok()
+        })
+        .collect()
+}
+
+/// A filter saved under a name in the settings.
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+pub struct RegionPreset {
+    pub name: String,
+    pub filter: RegionFilter,
+}
+
+impl RegionPreset {
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            RegionPreset {
+                name: "Thread stacks".to_string(),
+                filter: RegionFilter {
+                    thread_stacks: true,
+                    ..RegionFilter::default()
+                },
+            },
+            RegionPreset {
+                name: "Private anonymous".to_string(),
+                filter: RegionFilter {
+                    writable: Flag::Set,
+                    shared: Flag::Cleared,
+                    backing: Backing::Anonymous,
+                    ..RegionFilter::default()
+                },
+            },
+            RegionPreset {
+                name: "Without system libraries".to_string(),
+                filter: RegionFilter {
+                    writable: Flag::Set,
+                    exclude: r"^/(usr/)?lib".to_string(),
+                    ..RegionFilter::default()
+                },
+            },
+        ]
+    }
+
+    /// Loads, saves and deletes the `presets` of `filter` by `name`.
+    pub fn picker_for(
+        ui: &mut egui::Ui,
+        presets: &mut Vec<Self>,
+        filter: &mut RegionFilter,
+        name: &mut String,
+    ) {
+        ui.horizontal(|ui| {
+            ComboBox::from_label("Preset")
+                .selected_text(name.as_str())
+                .show_ui(ui, |ui| {
+                    for preset in presets.iter() {
+                        if ui
+                            .selectable_label(*name == preset.name, &preset.name)
+                            .clicked()
+                        {
+                            *name = preset.name.clone();
+                            *filter = preset.filter.clone();
+                        }
+                    }
+                });
+            ui.text_edit_singleline(name);
+        });
+        ui.horizontal(|ui| {
+            let existing = presets.iter().position(|preset| preset.name == *name);
+            ui.add_enabled(!name.is_empty(), egui::Button::new("Save Preset"))
+                .clicked()
+                .then(|| {
+                    let preset = Self {
+                        name: name.clone(),
+                        filter: filter.clone(),
+                    };
+                    match existing {
+                        Some(index) => presets[index] = preset,
+                        None => presets.push(preset),
+                    }
+                });
+            ui.add_enabled(existing.is_some(), egui::Button::new("Delete Preset"))
+                .clicked()
+                .then(|| {
+                    if let Some(index) = existing {
+                        presets.remove(index);
+                    }
+                });
+        });
+    }
+}
+
+#[cfg(test)]
+fn test_region(start: u64, end: u64, pathname: Option<&str>) -> MemoryRegion {
+    MemoryRegion {
+        start,
+        end,
+        readable: true,
+        writable: true,
+        executable: false,
+        shared: false,
+        pathname: pathname.map(str::to_string),
+        offset: 0x100,
+    }
+}
+
+#[test]
+fn test_parse_range() {
+    let parse = |range| parse_range(range).unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(parse("1000-2000"), 0x1000..0x2000);
+    assert_eq!(
+        parse(" 0x7f0000000000 - 0x7fffffffffff "),
+        0x7f0000000000..0x7fffffffffff
+    );
+    for range in ["", "1000", "2000-1000", "1000-1000", "1000-zz", "-2000"] {
+        assert!(parse_range(range).is_err(), "{}", range);
+    }
+}
+
+#[test]
+fn test_region_matcher() {
+    let maps = vec![
+        test_region(0x1000, 0x3000, Some("/opt/game/game")),
+        test_region(0x3000, 0x4000, None),
+        test_region(0x10000, 0x20000, Some("[heap]")),
+        MemoryRegion {
+            writable: false,
+            ..test_region(0x20000, 0x21000, Some("/usr/lib/libc.so.6"))
+        },
+        test_region(0x7000_0000, 0x7000_1000, Some("[stack:42]")),
+    ];
+    let select = |filter: RegionFilter| {
+        let matcher = filter.compile().unwrap_or_else(|err| panic!("{}", err));
+        matcher
+            .select(0, maps.clone())
+            .iter()
+            .map(|region| (region.start, region.end))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(select(RegionFilter::default()).len(), 5);
+    assert_eq!(
+        select(RegionFilter {
+            writable: Flag::Set,
+            backing: Backing::Anonymous,
+            ..RegionFilter::default()
+        }),
+        [
+            (0x3000, 0x4000),
+            (0x10000, 0x20000),
+            (0x7000_0000, 0x7000_1000)
+        ]
+    );
+    assert_eq!(
+        select(RegionFilter {
+            backing: Backing::File,
+            exclude: r"^/usr/lib".to_string(),
+            ..RegionFilter::default()
+        }),
+        [(0x1000, 0x3000)]
+    );
+    assert_eq!(
+        select(RegionFilter {
+            min_size: 0x2000,
+            max_size: 0x10000,
+            ..RegionFilter::default()
+        }),
+        [(0x1000, 0x3000), (0x10000, 0x20000)]
+    );
+    /* Named thread stacks count without looking up stack pointers */
+    assert_eq!(
+        select(RegionFilter {
+            thread_stacks: true,
+            ..RegionFilter::default()
+        }),
+        [(0x7000_0000, 0x7000_1000)]
+    );
+    assert_eq!(
+        select(RegionFilter {
+            address_ranges: "2000-3800, 1f000-20800".to_string(),
+            ..RegionFilter::default()
+        }),
+        [
+            (0x2000, 0x3000),
+            (0x3000, 0x3800),
+            (0x1f000, 0x20000),
+            (0x20000, 0x20800)
+        ]
+    );
+    assert!(
+        RegionFilter {
+            include: "(".to_string(),
+            ..RegionFilter::default()
+        }
+        .compile()
+        .is_err()
+    );
+}
+
+#[test]
+fn test_clip() {
+    let filter = RegionFilter {
+        address_ranges: "0-1800,2800-2900,5000-6000".to_string(),
+        ..RegionFilter::default()
+    };
+    let matcher = filter.compile().unwrap_or_else(|err| panic!("{}", err));
+    let clipped = matcher.clip(test_region(0x1000, 0x3000, Some("/opt/game/game")));
+    let parts: Vec<_> = clipped
+        .iter()
+        .map(|region| (region.start, region.end, region.offset))
+        .collect();
+    assert_eq!(parts, [(0x1000, 0x1800, 0x100), (0x2800, 0x2900, 0x1900)]);
+    assert!(
+        clipped
+            .iter()
+            .all(|region| region.pathname.as_deref() == Some("/opt/game/game"))
+    );
+
+    let unclipped = RegionFilter::default()
+        .compile()
+        .unwrap_or_else(|err| panic!("{}", err));
+    let region = test_region(0x1000, 0x3000, None);
+    assert_eq!(unclipped.clip(region.clone()), [region]);
+}
@@ -0,0 +1,183 @@
+use crate::{search::SearchResult, session::ScanStep};
+
+struct HistoryEntry {
+    step: ScanStep,
+    /// `None` for steps restored from a session, which cannot be undone to
+    results: Option<Vec<SearchResult>>,
+}
+
+/// The steps of the current scan with the results each of them produced, so
+/// refinements can be undone and redone.
+#[derive(Default)]
+pub struct ScanHistory {
+    entries: Vec<HistoryEntry>,
+    /// Number of applied entries, the ones after it can be redone
+    current: usize,
+}
+
+impl ScanHistory {
+    /// Replaces the history with steps whose results are unknown, except for
+    /// the `results` of the last one.
+    pub fn restore(steps: Vec<ScanStep>, results: &[SearchResult]) -> Self {
+        let count = steps.len();
+        let entries = steps
+            .into_iter()
+            .enumerate()
+            .map(|(index, step)| HistoryEntry {
+                step,
+                results: (index + 1 == count).then(|| results.to_vec()),
+            })
+            .collect();
+        Self {
+            entries,
+            current: count,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.current = 0;
+    }
+
+    /// Records a step and the results it produced, dropping the steps that
+    /// could be redone. `restart` begins a new scan.
+    pub fn push(&mut self, restart: bool, step: ScanStep, results: &[SearchResult]) {
+        if restart {
+            self.clear();
+        }
+        self.entries.truncate(self.current);
+        self.entries.push(HistoryEntry {
+            step,
+            results: Some(results.to_vec()),
+        });
+        self.current = self.entries.len();
+    }
+
+    /// The applied steps, oldest first.
+    pub fn steps(&self) -> Vec<ScanStep> {
+        self.entries[..self.current]
+            .iter()
+            .map(|entry| entry.step.clone())
+            .collect()
+    }
+
+    /// All steps and whether they are applied.
+    pub fn entries(&self) -> impl Iterator<Item = (&ScanStep, bool)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| (&entry.step, index < self.current))
+    }
+
+    pub fn can_undo(&self) -> bool {
+        match self.current {
+            0 => false,
+            1 => true,
+            current => self.entries[current - 2].results.is_some(),
+        }
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.current < self.entries.len()
+    }
+
+    /// Steps back and returns the results before the last applied step.
+    pub fn undo(&mut self) -> Option<Vec<SearchResult>> {
+        if !self.can_undo() {
+            return None;
+        }
+        self.current -= 1;
+        Some(self.results_at(self.current))
+    }
+
+    /// Applies the next step again and returns its results.
+    pub fn redo(&mut self) -> Option<Vec<SearchResult>> {
+        if !self.can_redo() {
+            return None;
+        }
+        self.current += 1;
+        Some(self.results_at(self.current))
+    }
+
+    /// Results after the first `applied` steps.
+    fn results_at(&self, applied: usize) -> Vec<SearchResult> {
+        match applied.checked_sub(1) {
+            Some(index) => self.entries[index].results.clone().unwrap_or_default(),
+            None => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+fn step(value: &str) -> ScanStep {
+    ScanStep {
+        operator: "=".to_string(),
+        value: value.to_string(),
+        data_type: crate::data_types::DataType::I32,
+    }
+}
+
+/// Results of one process, told apart by their pid.
+#[cfg(test)]
+fn results(pid: i32) -> Vec<SearchResult> {
+    vec![SearchResult {
+        pid,
+        data_type: crate::data_types::DataType::I32,
+        pointers: memscan::address_set::AddressSet::new(),
+    }]
+}
+
+#[cfg(test)]
+fn pids(results: Option<Vec<SearchResult>>) -> Option<Vec<i32>> {
+    results.map(|results| results.iter().map(|result| result.pid).collect())
+}
+
+#[test]
+fn test_undo_redo() {
+    let mut history = ScanHistory::default();
+    assert!(!history.can_undo());
+    assert!(history.undo().is_none());
+
+    history.push(true, step("1"), &results(1));
+    history.push(false, step("2"), &results(2));
+    assert_eq!(pids(history.undo()), Some(vec![1]));
+    assert_eq!(pids(history.undo()), Some(vec![]));
+    assert!(!history.can_undo());
+    assert_eq!(pids(history.redo()), Some(vec![1]));
+    assert_eq!(pids(history.redo()), Some(vec![2]));
+    assert!(!history.can_redo());
+    assert!(history.redo().is_none());
+    assert_eq!(history.steps().len(), 2);
+}
+
+#[test]
+fn test_push_truncates() {
+    let mut history = ScanHistory::default();
+    history.push(true, step("1"), &results(1));
+    history.push(false, step("2"), &results(2));
+    history.push(false, step("3"), &results(3));
+    history.undo();
+    history.undo();
+
+    /* A new step replaces the undone ones */
+    history.push(false, step("4"), &results(4));
+    assert!(!history.can_redo());
+    let values: Vec<String> = history.steps().into_iter().map(|step| step.value).collect();
+    assert_eq!(values, ["1", "4"]);
+    assert_eq!(pids(history.undo()), Some(vec![1]));
+
+    /* Restarting drops everything */
+    history.push(true, step("5"), &results(5));
+    assert_eq!(history.entries().count(), 1);
+    assert_eq!(pids(history.undo()), Some(vec![]));
+}
+
+#[test]
+fn test_restore() {
+    let mut history = ScanHistory::restore(vec![step("1"), step("2")], &results(2));
+    /* Only the results of the last step are known */
+    assert!(!history.can_undo());
+    history.push(false, step("3"), &results(3));
+    assert_eq!(pids(history.undo()), Some(vec![2]));
+    assert!(!history.can_undo());
+}
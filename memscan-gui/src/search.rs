@@ -1,12 +1,20 @@
-use memscan::search::MemorySearch;
-use nix::libc::{iovec, process_vm_readv};
+use std::{collections::HashMap, ops::Range};
+
+use memscan::{
+    address_set::AddressSet,
+    memory_source::{MemorySource, is_unreadable, read_pages},
+    pagemap::{self, Pagemap},
+    search::relative::Relation,
+};
 
 use crate::{
     app_error::AppError,
     data_types::{DataType, WrappedValue},
     endian::Endianness,
+    module_address::{ModuleAddress, map_path},
+    process_memory,
     process_picker::Process,
-    search_scope,
+    region_filter::RegionMatcher,
     settings::Settings,
 };
 
@@ -14,144 +22,617 @@ pub struct SearchRegion {
     pub pid: i32,
     pub start: u64,
     pub end: u64,
+    /// File the region maps, if any
+    pub pathname: Option<String>,
+    /// Offset of `start` into `pathname`
+    pub offset: u64,
 }
 
 impl SearchRegion {
-    pub fn load(processes: &[Process], search_scope: search_scope::SearchScope) -> Vec<Self> {
+    pub fn load(processes: &[Process], matcher: &RegionMatcher) -> Vec<Self> {
         let mut regions = vec![];
         for process in processes {
-            let proc = procfs::process::Process::new(process.pid).unwrap();
-            let maps = proc.maps().unwrap();
-            for map in maps {
-                if search_scope.is_in_scope(&map) {
-                    regions.push(Self {
-                        pid: process.pid,
-                        start: map.address.0,
-                        end: map.address.1,
-                    });
+            let maps = match process_memory::selected_regions(process.pid, matcher) {
+                Ok(maps) => maps,
+                Err(error) => {
+                    println!("[ERROR]: Failed to load regions of PID {}: {}", process.pid, error);
+                    continue;
                 }
+            };
+            for map in maps {
+                regions.push(Self {
+                    pid: process.pid,
+                    start: map.start,
+                    end: map.end,
+                    pathname: map_path(&map),
+                    offset: map.offset,
+                });
             }
         }
         regions
     }
+
+    pub fn module_address(&self, address: u64) -> Option<ModuleAddress> {
+        let path = self.pathname.clone()?;
+        Some(ModuleAddress::new(path, self.start, self.offset, address))
+    }
+
+    /// The module relative form of `address` if it lies in one of `regions`.
+    pub fn module_address_in(regions: &[Self], pid: i32, address: u64) -> Option<ModuleAddress> {
+        regions
+            .iter()
+            .find(|region| region.pid == pid && region.start <= address && address < region.end)?
+            .module_address(address)
+    }
 }
 
+/// How much memory a scan read, and which ranges it couldn't read.
+#[derive(Default)]
+pub struct ScanCoverage {
+    pub scanned: u64,
+    pub skipped: u64,
+    /// PID and address range of unreadable memory, adjacent ranges merged
+    pub unreadable: Vec<(i32, Range<u64>)>,
+    /// PID and end of the last counted chunk, so overlapping chunks count once
+    counted: Option<(i32, u64)>,
+}
+
+impl ScanCoverage {
+    /// Counts a chunk of `length` bytes at `start` of which `parts` were read.
+    fn record(&mut self, pid: i32, start: u64, length: usize, parts: &[Range<usize>]) {
+        let end = start + length as u64;
+        let from = match self.counted {
+            Some((counted_pid, counted_end)) if counted_pid == pid => counted_end.max(start),
+            _ => start,
+        };
+        let mut position = from;
+        let read = parts
+            .iter()
+            .map(|part| start + part.start as u64..start + part.end as u64);
+        /* The empty range at the end closes the last gap */
+        for part in read.chain(std::iter::once(end..end)) {
+            self.skip(pid, position..part.start);
+            self.scanned += part.end.saturating_sub(part.start.max(from));
+            position = position.max(part.end);
+        }
+        self.counted = Some((pid, end.max(from)));
+    }
+
+    fn skip(&mut self, pid: i32, range: Range<u64>) {
+        if range.is_empty() {
+            return;
+        }
+        self.skipped += range.end - range.start;
+        match self.unreadable.last_mut() {
+            Some((last_pid, last)) if *last_pid == pid && last.end == range.start => {
+                last.end = range.end;
+            }
+            _ => self.unreadable.push((pid, range)),
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "Scanned {}, {} unreadable in {} ranges",
+            format_size(self.scanned),
+            format_size(self.skipped),
+            self.unreadable.len()
+        )
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = next;
+    }
+    if unit == "B" {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, unit)
+    }
+}
+
+/// Addresses in one process that matched a search as `data_type`.
+#[derive(Clone)]
+pub struct SearchResult {
+    pub pid: i32,
+    pub data_type: DataType,
+    pub pointers: AddressSet,
+}
+
+/// Runs `scan` on the regions or results of every process in turn, with the
+/// memory source of that process. Scanners only see one process at a time.
+pub fn by_process<T>(
+    items: &[T],
+    pid: impl Fn(&T) -> i32,
+    mut scan: impl FnMut(&dyn MemorySource, &[T]) -> Result<Vec<SearchResult>, AppError>,
+) -> Result<Vec<SearchResult>, AppError> {
+    let mut results = vec![];
+    for items in items.chunk_by(|a, b| pid(a) == pid(b)) {
+        let source = process_memory::source(pid(&items[0]));
+        results.extend(scan(&*source, items)?);
+    }
+    Ok(results)
+}
+
+/// Searches `regions`, which all belong to the process `source` reads.
 pub fn search_sync(
+    source: &dyn MemorySource,
     regions: &[SearchRegion],
     data_type: DataType,
     input: &str,
     settings: &Settings,
     endianness: Endianness,
-) -> Result<Vec<(i32, Vec<u64>)>, AppError> {
+    coverage: &mut ScanCoverage,
+) -> Result<Vec<SearchResult>, AppError> {
     let mut results = vec![];
 
     let step_size: usize = settings.search_buffer_size;
     let mut read_buffer = vec![0; step_size];
-    let wrapped_value = data_type.parse(input)?;
+    let wrapped_values = data_type.parse_candidates(input)?;
+    let zeros_match = wrapped_values
+        .iter()
+        .any(|value| value.compare_to(&[0; 16], endianness));
+
+    for region in regions {
+        let mut pointers = vec![AddressSet::new(); wrapped_values.len()];
+        let patterns: Option<Vec<_>> = wrapped_values
+            .iter()
+            .map(|value| value.search_pattern(endianness))
+            .collect();
+        if let Some(patterns) = patterns.filter(|_| source.is_remote()) {
+            /* The agent scans itself so only the matches cross the network */
+            for ((needle, alignment), pointers) in patterns.iter().zip(&mut pointers) {
+                *pointers = source.find_bytes(region.start, region.end, needle, *alignment)?;
+            }
+        } else {
+            for range in populated_ranges(region, settings, zeros_match) {
+                for chunk_start in range.clone().step_by(step_size) {
+                    let chunk_end = (chunk_start + step_size as u64).min(range.end);
+                    let chunk_length = (chunk_end - chunk_start) as usize;
+
+                    let buffer = &mut read_buffer[..chunk_length];
+                    for part in read_chunk(source, region.pid, chunk_start, buffer, coverage)? {
+                        let part_start = chunk_start + part.start as u64;
+                        let memory = &buffer[part];
+                        for (wrapped_value, pointers) in wrapped_values.iter().zip(&mut pointers) {
+                            for offset in wrapped_value.scan_memory(memory, endianness) {
+                                pointers.push(part_start + offset as u64);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        for (wrapped_value, pointers) in wrapped_values.iter().zip(pointers) {
+            if !pointers.is_empty() {
+                results.push(SearchResult {
+                    pid: region.pid,
+                    data_type: wrapped_value.data_type(),
+                    pointers,
+                });
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+/// The parts of `region` worth reading. Pages of anonymous mappings that were
+/// never written to hold only zeros, so they are skipped unless zeros match.
+fn populated_ranges(
+    region: &SearchRegion,
+    settings: &Settings,
+    zeros_match: bool,
+) -> Vec<Range<u64>> {
+    let whole = region.start..region.end;
+    /* Untouched pages of mapped files read as the file, not as zeros */
+    if zeros_match || region.pid < 0 || region.pathname.is_some() {
+        return vec![whole];
+    }
+    let populated = Pagemap::open(region.pid).and_then(|pagemap| {
+        pagemap.populated(region.start, region.end, settings.scan_swapped_pages)
+    });
+    match populated {
+        Ok(ranges) => ranges,
+        Err(error) => {
+            let error = AppError::from(error);
+            println!("[WARN]: Failed to read pagemap of PID {}: {}", region.pid, error);
+            vec![whole]
+        }
+    }
+}
+
+/// Reads `buffer.len()` bytes at `start` of process `pid` and returns the
+/// parts of `buffer` that were read. Unreadable pages are counted as skipped,
+/// other errors like the process exiting are returned.
+pub fn read_chunk(
+    source: &dyn MemorySource,
+    pid: i32,
+    start: u64,
+    buffer: &mut [u8],
+    coverage: &mut ScanCoverage,
+) -> Result<Vec<Range<usize>>, AppError> {
+    let parts = read_pages(source, start, buffer, pagemap::page_size())?;
+    coverage.record(pid, start, buffer.len(), &parts);
+    Ok(parts)
+}
+
+/// Fills `buffer` from `pointer`, `false` if that memory can't be read.
+/// Other errors like the process exiting are returned.
+fn read_exact_at(
+    source: &dyn MemorySource,
+    pointer: u64,
+    buffer: &mut [u8],
+) -> Result<bool, AppError> {
+    match source.read(pointer, buffer) {
+        Ok(read) => Ok(read == buffer.len()),
+        Err(error) if is_unreadable(&error) => Ok(false),
+        Err(error) => Err(error.into()),
+    }
+}
+
+pub struct GroupComponent {
+    pub data_type: DataType,
+    pub value: String,
+}
+
+/// Largest window a grouped search accepts, chunks are read with it as overlap.
+pub const MAX_GROUP_DISTANCE: u64 = 4096;
+
+/// Where the components of a grouped search may lie relative to each other.
+#[derive(Clone, Copy)]
+pub struct GroupWindow {
+    /// Bytes from the start of the first component to the end of the last
+    pub distance: u64,
+    /// Components follow each other in the given order
+    pub ordered: bool,
+}
+
+pub fn search_grouped_sync(
+    source: &dyn MemorySource,
+    regions: &[SearchRegion],
+    components: &[GroupComponent],
+    window: GroupWindow,
+    settings: &Settings,
+    endianness: Endianness,
+    coverage: &mut ScanCoverage,
+) -> Result<Vec<SearchResult>, AppError> {
+    let mut results = vec![];
+    let GroupWindow {
+        distance: max_distance,
+        ordered,
+    } = window;
+
+    if components.is_empty() {
+        return Ok(results);
+    }
+    if max_distance > MAX_GROUP_DISTANCE {
+        return Err(AppError::InvalidInput(format!(
+            "Group window is limited to {} bytes",
+            MAX_GROUP_DISTANCE
+        )));
+    }
+
+    let wrapped_values = components
+        .iter()
+        .map(|component| component.data_type.parse(&component.value))
+        .collect::<Result<Vec<_>, _>>()?;
+    let sizes: Vec<u64> = components
+        .iter()
+        .map(|component| component.data_type.size() as u64)
+        .collect();
+
+    /* Chunks overlap by the group window so groups crossing a chunk border are found */
+    let step_size: usize = settings.search_buffer_size;
+    let overlap = max_distance as usize;
+    let mut read_buffer = vec![0; step_size + overlap];
 
     for region in regions {
-        let mut pointers = vec![];
+        let mut pointers = AddressSet::new();
         for chunk_start in (region.start..region.end).step_by(step_size) {
-            let chunk_end = (chunk_start + step_size as u64).min(region.end);
+            let chunk_end = (chunk_start + (step_size + overlap) as u64).min(region.end);
             let chunk_length = (chunk_end - chunk_start) as usize;
-            println!("Reading from {:X} to {:X}", chunk_start, chunk_end);
-
-            let local_iov = [iovec {
-                iov_base: read_buffer.as_mut_ptr() as *mut _,
-                iov_len: read_buffer.len(),
-            }];
-            let remote_iov = [iovec {
-                iov_base: chunk_start as *mut _,
-                iov_len: (chunk_length) as usize,
-            }];
-
-            let read_size =
-                unsafe { process_vm_readv(region.pid, &local_iov as _, 1, &remote_iov as _, 1, 0) };
-
-            if read_size < 0 {
-                // return Err(AppError::from_errno());
-                let error = AppError::from_errno();
-                println!(
-                    "[ERROR]: Failed to read memory for PID {}: {}",
-                    region.pid, error
-                );
-                continue;
+
+            let buffer = &mut read_buffer[..chunk_length];
+            for part in read_chunk(source, region.pid, chunk_start, buffer, coverage)? {
+                let hits: Vec<Vec<u64>> = wrapped_values
+                    .iter()
+                    .map(|value| {
+                        value
+                            .scan_memory(&buffer[part.clone()], endianness)
+                            .map(|offset| (part.start + offset) as u64)
+                            .collect()
+                    })
+                    .collect();
+
+                /* Bases inside the overlap belong to the next chunk */
+                for base in merge_groups(&hits, &sizes, max_distance, ordered) {
+                    if base < step_size as u64 {
+                        pointers.push(chunk_start + base);
+                    }
+                }
             }
-    
-            if read_size != chunk_length as isize {
-                println!(
-                    "[WARN]: Failed to read entire memory: {} vs. {}",
-                    read_size, chunk_length
-                );
+        }
+        if !pointers.is_empty() {
+            results.push(SearchResult {
+                pid: region.pid,
+                data_type: components[0].data_type,
+                pointers,
+            });
+        }
+    }
+
+    Ok(results)
+}
+
+/// Finds every base offset at which all components fit into a window of
+/// `max_distance` bytes. `hits` holds the sorted offsets of each component.
+/// When `ordered` is set, the components have to follow each other in the
+/// given order without overlapping, starting with the first at the base.
+pub fn merge_groups(
+    hits: &[Vec<u64>],
+    sizes: &[u64],
+    max_distance: u64,
+    ordered: bool,
+) -> Vec<u64> {
+    let mut bases = vec![];
+
+    if hits.iter().any(|offsets| offsets.is_empty()) {
+        return bases;
+    }
+
+    if ordered {
+        for &base in &hits[0] {
+            let mut next = base + sizes[0];
+            let complete = hits[1..].iter().zip(&sizes[1..]).all(|(offsets, size)| {
+                let index = offsets.partition_point(|&offset| offset < next);
+                match offsets.get(index) {
+                    Some(&offset) if offset + size <= base + max_distance => {
+                        next = offset + size;
+                        true
+                    }
+                    _ => false,
+                }
+            });
+            if complete {
+                bases.push(base);
             }
+        }
+        return bases;
+    }
+
+    /* Any component may start the group, the lowest address is reported */
+    let mut candidates: Vec<u64> = hits.iter().flatten().copied().collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+
+    let mut placed = vec![];
+    for base in candidates {
+        placed.clear();
+        if place_components(hits, sizes, base, base + max_distance, &mut placed) {
+            bases.push(base);
+        }
+    }
+
+    bases
+}
 
-            for offset in wrapped_value.scan_memory(&read_buffer, endianness) {
-                pointers.push(chunk_start + offset as u64);
+/// Places the components after those in `placed` at offsets within
+/// `base..end` without overlapping each other, backtracking when a component
+/// finds no room. One of them has to start at `base`.
+fn place_components(
+    hits: &[Vec<u64>],
+    sizes: &[u64],
+    base: u64,
+    end: u64,
+    placed: &mut Vec<Range<u64>>,
+) -> bool {
+    let component = placed.len();
+    let at_base = |offsets: &Vec<u64>| offsets.binary_search(&base).is_ok();
+    if !placed.iter().any(|range| range.start == base) && !hits[component..].iter().any(at_base)
+    {
+        return false;
+    }
+    let Some(offsets) = hits.get(component) else {
+        return true;
+    };
+    let size = sizes[component];
+
+    let index = offsets.partition_point(|&offset| offset < base);
+    for &offset in offsets[index..].iter().take_while(|&&offset| offset + size <= end) {
+        let range = offset..offset + size;
+        if placed
+            .iter()
+            .any(|other| range.start < other.end && other.start < range.end)
+        {
+            continue;
+        }
+        placed.push(range);
+        if place_components(hits, sizes, base, end, placed) {
+            return true;
+        }
+        placed.pop();
+    }
+    false
+}
+
+/// Parses the comma separated values of a sequence search.
+pub fn parse_sequence(data_type: DataType, input: &str) -> Result<Vec<WrappedValue>, AppError> {
+    data_type.check_relative()?;
+    let values = input
+        .split(',')
+        .map(|value| data_type.parse(value.trim()))
+        .collect::<Result<Vec<_>, _>>()?;
+    if values.len() < 2 {
+        return Err(AppError::DataTypeParseError(
+            "Relative search needs at least two values".to_string(),
+        ));
+    }
+    Ok(values)
+}
+
+/// Finds `values` from `parse_sequence` stored next to each other under an
+/// unknown `relation`.
+pub fn search_relative_sync(
+    source: &dyn MemorySource,
+    regions: &[SearchRegion],
+    relation: Relation,
+    values: &[WrappedValue],
+    settings: &Settings,
+    endianness: Endianness,
+    coverage: &mut ScanCoverage,
+) -> Result<Vec<SearchResult>, AppError> {
+    let mut results = vec![];
+    let Some(data_type) = values.first().map(|value| value.data_type()) else {
+        return Ok(results);
+    };
+
+    /* Chunks overlap by the sequence length so no sequence is cut in half */
+    let step_size: usize = settings.search_buffer_size;
+    let overlap = (values.len() - 1) * data_type.size();
+    let mut read_buffer = vec![0; step_size + overlap];
+
+    for region in regions {
+        let mut pointers = AddressSet::new();
+        for chunk_start in (region.start..region.end).step_by(step_size) {
+            let chunk_end = (chunk_start + (step_size + overlap) as u64).min(region.end);
+            let chunk_length = (chunk_end - chunk_start) as usize;
+
+            let buffer = &mut read_buffer[..chunk_length];
+            for part in read_chunk(source, region.pid, chunk_start, buffer, coverage)? {
+                /* Sequences starting inside the overlap belong to the next chunk */
+                let memory = &buffer[part.clone()];
+                for offset in WrappedValue::scan_relative(relation, values, memory, endianness)? {
+                    let offset = part.start + offset;
+                    if offset < step_size {
+                        pointers.push(chunk_start + offset as u64);
+                    }
+                }
             }
         }
         if !pointers.is_empty() {
-            results.push((region.pid, pointers));
+            results.push(SearchResult {
+                pid: region.pid,
+                data_type,
+                pointers,
+            });
         }
     }
 
     Ok(results)
 }
 
+/// Last value and the one before it for every result, used to refine by deltas.
+/// Results of different types at the same address have their own history.
+pub type ValueHistory = HashMap<(i32, DataType, u64), (WrappedValue, Option<WrappedValue>)>;
+
+/// Keeps results that changed by the same delta as between the previous two
+/// refinements. Results without enough history are kept until they have some,
+/// those that can't be read any more are dropped.
+pub fn search_same_delta_sync(
+    results: &[SearchResult],
+    relation: Relation,
+    history: &mut ValueHistory,
+    endianness: Endianness,
+) -> Result<Vec<SearchResult>, AppError> {
+    let mut new_results = vec![];
+    let mut new_history = ValueHistory::new();
+
+    for result in results {
+        let pid = &result.pid;
+        let mut new_pointers = AddressSet::new();
+        let source = process_memory::source(*pid);
+        let mut read_buffer = vec![0u8; result.data_type.size()];
+
+        for pointer in &result.pointers {
+            if !read_exact_at(&*source, pointer, &mut read_buffer)? {
+                continue;
+            }
+            let current = result.data_type.cast(&read_buffer, endianness)?;
+
+            let matches = match history.get(&(*pid, result.data_type, pointer)) {
+                Some((last, Some(previous))) => {
+                    WrappedValue::changed_by_same_delta(relation, *previous, *last, current)
+                }
+                _ => true,
+            };
+
+            if matches {
+                let last = history.get(&(*pid, result.data_type, pointer)).map(|(last, _)| *last);
+                new_history.insert((*pid, result.data_type, pointer), (current, last));
+                new_pointers.push(pointer);
+            }
+        }
+
+        if !new_pointers.is_empty() {
+            new_results.push(SearchResult {
+                pid: *pid,
+                data_type: result.data_type,
+                pointers: new_pointers,
+            });
+        }
+    }
+
+    *history = new_history;
+    Ok(new_results)
+}
+
+/// Refines every result with its own type. With `DataType::Any` results of
+/// a type the input doesn't fit are dropped, otherwise that is an error.
+/// Values that can't be read any more are dropped and counted as skipped.
+/// `results` all belong to the process `source` reads.
 pub fn search_continue_sync(
-    results: &[(i32, Vec<u64>)],
+    source: &dyn MemorySource,
+    results: &[SearchResult],
     data_type: DataType,
     input: &str,
-    settings: &Settings,
     endianness: Endianness,
-) -> Result<Vec<(i32, Vec<u64>)>, AppError> {
+    coverage: &mut ScanCoverage,
+) -> Result<Vec<SearchResult>, AppError> {
     let mut new_results = vec![];
 
-    let size = data_type.size();
-    let mut read_buffer = vec![0u8; size];
-    let wrapped_value = data_type.parse(input)?;
-
-    for (pid, pointers) in results.iter() {
-        let mut new_pointers = vec![];
-
-        for pointer in pointers {
-            let local_iov = [iovec {
-                iov_base: read_buffer.as_mut_ptr() as *mut _,
-                iov_len: read_buffer.len(),
-            }];
-            let remote_iov = [iovec {
-                iov_base: *pointer as *mut _,
-                iov_len: size as usize,
-            }];
-
-            let read_size =
-                unsafe { process_vm_readv(*pid, &local_iov as _, 1, &remote_iov as _, 1, 0) };
-
-            if read_size < 0 {
-                // return Err(AppError::from_errno());
-                let error = AppError::from_errno();
-                println!(
-                    "[ERROR]: Failed to read memory for PID {}: {}",
-                    pid, error
-                );
-                continue;
-            }
+    for result in results {
+        /* Values of one result may overlap, those of different results are
+        counted on their own */
+        coverage.counted = None;
+        let pid = &result.pid;
+        let size = result.data_type.size();
+        let mut read_buffer = vec![0u8; size];
+        let wrapped_value = match result.data_type.parse(input) {
+            Ok(value) => value,
+            Err(_) if data_type == DataType::Any => continue,
+            Err(error) => return Err(error),
+        };
+        let mut new_pointers = AddressSet::new();
 
-            if read_size != size as isize {
-                println!(
-                    "[WARN]: Failed to read entire memory: {} vs. {}",
-                    read_size, size
-                );
+        for pointer in &result.pointers {
+            let read = read_exact_at(source, pointer, &mut read_buffer)?;
+            coverage.record(*pid, pointer, size, read.then_some(0..size).as_slice());
+            if !read {
+                continue;
             }
 
             let matches = wrapped_value.compare_to(&read_buffer, endianness);
 
             if matches {
-                new_pointers.push(*pointer);
+                new_pointers.push(pointer);
             }
         }
 
         if !new_pointers.is_empty() {
-            new_results.push((*pid, new_pointers));
+            new_results.push(SearchResult {
+                pid: *pid,
+                data_type: result.data_type,
+                pointers: new_pointers,
+            });
         }
     }
 
@@ -159,32 +640,205 @@ pub fn search_continue_sync(
 }
 
 pub fn read_value(pid: i32, pointer: u64, data_type: DataType) -> Result<WrappedValue, AppError> {
+    read_value_in(
+        &*process_memory::source(pid),
+        pointer,
+        data_type,
+        Endianness::Native,
+    )
+}
+
+pub fn read_value_in(
+    source: &dyn MemorySource,
+    pointer: u64,
+    data_type: DataType,
+    endianness: Endianness,
+) -> Result<WrappedValue, AppError> {
     let size = data_type.size();
     let mut read_buffer = vec![0u8; size];
 
-    let local_iov = [iovec {
-        iov_base: read_buffer.as_mut_ptr() as *mut _,
-        iov_len: read_buffer.len(),
-    }];
-    let remove_iov = [iovec {
-        iov_base: pointer as *mut _,
-        iov_len: size as usize,
-    }];
+    let read_size = source.read(pointer, &mut read_buffer)?;
+    if read_size != size {
+        return Err(AppError::Io(format!(
+            "Only {} of {} bytes at {:#x} could be read",
+            read_size, size, pointer
+        )));
+    }
 
-    let read_size = unsafe { process_vm_readv(pid, &local_iov as _, 1, &remove_iov as _, 1, 0) };
+    let wrapped_value = data_type.cast(&read_buffer, endianness)?;
 
-    if read_size < 0 {
-        return Err(AppError::from_errno());
-    }
+    Ok(wrapped_value)
+}
 
-    if read_size != size as isize {
+pub fn write_value(pid: i32, pointer: u64, value: WrappedValue) -> Result<(), AppError> {
+    let write_buffer = value.to_ne_bytes();
+
+    let write_size = process_memory::source(pid).write(pointer, &write_buffer)?;
+
+    if write_size != write_buffer.len() {
         println!(
-            "[WARN]: Failed to read entire memory: {} vs. {}",
-            read_size, size
+            "[WARN]: Failed to write entire value: {} vs. {}",
+            write_size,
+            write_buffer.len()
         );
     }
 
-    let wrapped_value = data_type.cast(&read_buffer, Endianness::Native)?;
+    Ok(())
+}
+
+/// Memory of a pseudo process with a few `i32` values, and a region reaching
+/// one page past the mapped memory.
+#[cfg(test)]
+fn test_memory() -> (memscan::memory_source::BufferSource, Vec<SearchRegion>, Settings) {
+    let mut memory = vec![0u8; 0x3000];
+    memory[0x100..0x104].copy_from_slice(&1234i32.to_ne_bytes());
+    memory[0x2800..0x2804].copy_from_slice(&1234i32.to_ne_bytes());
+    memory[0x204..0x206].copy_from_slice(&7i16.to_ne_bytes());
+    let mut source = memscan::memory_source::BufferSource::new();
+    source.map(0x10000, memory);
 
-    Ok(wrapped_value)
+    let regions = vec![SearchRegion {
+        pid: -1,
+        start: 0x10000,
+        end: 0x14000,
+        pathname: None,
+        offset: 0,
+    }];
+    let mut settings = Settings::default();
+    settings.search_buffer_size = 0x1000;
+    (source, regions, settings)
+}
+
+#[test]
+fn test_search_sync() {
+    let (mut source, regions, settings) = test_memory();
+    let mut coverage = ScanCoverage::default();
+    let search = |source: &dyn MemorySource, input, coverage: &mut ScanCoverage| {
+        search_sync(
+            source,
+            &regions,
+            DataType::I32,
+            input,
+            &settings,
+            Endianness::Native,
+            coverage,
+        )
+        .unwrap_or_else(|err| panic!("{}", err))
+    };
+    let results = search(&source, "1234", &mut coverage);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].pid, -1);
+    assert_eq!(results[0].pointers.iter().collect::<Vec<_>>(), [0x10100, 0x12800]);
+    assert_eq!(coverage.scanned, 0x3000);
+    assert_eq!(coverage.unreadable, [(-1, 0x13000..0x14000)]);
+
+    /* Refining reads the values again */
+    source.write(0x12800, &5678i32.to_ne_bytes()).unwrap();
+    let mut coverage = ScanCoverage::default();
+    let results = search_continue_sync(
+        &source,
+        &results,
+        DataType::I32,
+        "1234",
+        Endianness::Native,
+        &mut coverage,
+    )
+    .unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(results[0].pointers.iter().collect::<Vec<_>>(), [0x10100]);
+    assert_eq!(coverage.scanned, 8);
+
+    assert!(search(&source, "4321", &mut coverage).is_empty());
+}
+
+#[test]
+fn test_merge_groups() {
+    /* X may only take 8 once Y took 0 */
+    let hits = [vec![0, 8], vec![0]];
+    assert_eq!(merge_groups(&hits, &[4, 4], 16, false), [0]);
+    assert!(merge_groups(&hits, &[4, 4], 16, true).is_empty());
+    assert!(merge_groups(&hits, &[4, 4], 8, false).is_empty());
+
+    /* Components may not share bytes */
+    let hits = [vec![0], vec![2]];
+    assert!(merge_groups(&hits, &[4, 4], 16, false).is_empty());
+    assert_eq!(merge_groups(&hits, &[2, 4], 16, false), [0]);
+
+    /* The group is reported at its lowest component whatever the order */
+    let hits = [vec![0x20], vec![0x10, 0x40]];
+    assert_eq!(merge_groups(&hits, &[4, 4], 0x20, false), [0x10]);
+    assert_eq!(merge_groups(&hits, &[4, 4], 0x24, false), [0x10, 0x20]);
+    assert!(merge_groups(&hits, &[4, 4], 0x20, true).is_empty());
+    assert_eq!(merge_groups(&hits, &[4, 4], 0x24, true), [0x20]);
+    assert!(merge_groups(&[vec![0], vec![]], &[4, 4], 16, false).is_empty());
+}
+
+#[test]
+fn test_search_grouped_sync() {
+    let (source, regions, settings) = test_memory();
+    let components = [
+        GroupComponent {
+            data_type: DataType::I16,
+            value: "7".to_string(),
+        },
+        GroupComponent {
+            data_type: DataType::I32,
+            value: "1234".to_string(),
+        },
+    ];
+    let search = |distance, ordered| {
+        let window = GroupWindow { distance, ordered };
+        let mut coverage = ScanCoverage::default();
+        search_grouped_sync(
+            &source,
+            &regions,
+            &components,
+            window,
+            &settings,
+            Endianness::Native,
+            &mut coverage,
+        )
+    };
+
+    let results = search(0x200, false).unwrap_or_else(|err| panic!("{}", err));
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].pointers.iter().collect::<Vec<_>>(), [0x10100]);
+    let results = search(0x200, true).unwrap_or_else(|err| panic!("{}", err));
+    assert!(results.is_empty());
+    let results = search(0x100, false).unwrap_or_else(|err| panic!("{}", err));
+    assert!(results.is_empty());
+
+    assert!(matches!(
+        search(MAX_GROUP_DISTANCE + 1, false),
+        Err(AppError::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn test_search_relative_sync() {
+    let (mut source, regions, settings) = test_memory();
+    /* 5, 9 stored big endian with k = 0x100 */
+    source.write(0x10400, &[0x01, 0x05, 0x01, 0x09]).unwrap();
+    let values = parse_sequence(DataType::U16, "5, 9").unwrap_or_else(|err| panic!("{}", err));
+    let search = |endianness| {
+        let mut coverage = ScanCoverage::default();
+        search_relative_sync(
+            &source,
+            &regions,
+            Relation::Offset,
+            &values,
+            &settings,
+            endianness,
+            &mut coverage,
+        )
+        .unwrap_or_else(|err| panic!("{}", err))
+    };
+
+    let results = search(Endianness::Big);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].data_type, DataType::U16);
+    assert_eq!(results[0].pointers.iter().collect::<Vec<_>>(), [0x10400]);
+    assert!(!search(Endianness::Little).iter().any(|result| result.pointers.contains(0x10400)));
+
+    assert!(parse_sequence(DataType::U16, "5").is_err());
+    assert!(parse_sequence(DataType::F32, "5, 9").is_err());
 }
@@ -1,7 +1,8 @@
 use std::fmt::Display;
 
 use egui::ComboBox;
-use procfs::process::{MMPermissions, MMapPath, MemoryMap};
+
+use crate::region_filter::{Flag, RegionFilter};
 
 #[derive(Debug, PartialEq, Default, Clone, Copy, serde::Deserialize, serde::Serialize)]
 pub enum SearchScope {
@@ -10,6 +11,8 @@ pub enum SearchScope {
     #[default]
     Both,
     All,
+    /// The filter built in the search panel
+    Custom,
 }
 
 impl Display for SearchScope {
@@ -19,6 +22,7 @@ impl Display for SearchScope {
             SearchScope::Heap => write!(f, "Heap"),
             SearchScope::Both => write!(f, "Stack & Heap"),
             SearchScope::All => write!(f, "All Writeable"),
+            SearchScope::Custom => write!(f, "Custom Filter"),
         }
     }
 }
@@ -32,15 +36,63 @@ impl SearchScope {
                 ui.selectable_value(self, Self::Heap, "Heap");
                 ui.selectable_value(self, Self::Both, "Stack & Heap");
                 ui.selectable_value(self, Self::All, "All Writeable");
+                ui.selectable_value(self, Self::Custom, "Custom Filter");
             });
     }
 
-    pub fn is_in_scope(self, map: &MemoryMap) -> bool {
+    /// The regions the scope stands for, `custom` for `Custom`.
+    pub fn filter(self, custom: &RegionFilter) -> RegionFilter {
         match self {
-            SearchScope::Stack => map.pathname == MMapPath::Stack,
-            SearchScope::Heap => map.pathname == MMapPath::Heap,
-            SearchScope::Both => map.pathname == MMapPath::Heap || map.pathname == MMapPath::Stack,
-            SearchScope::All => map.perms & MMPermissions::READ != MMPermissions::empty(),
+            /* Older kernels name thread stacks `[stack:<tid>]` */
+            SearchScope::Stack => RegionFilter::pathname(r"^\[stack(:\d+)?\]$"),
+            SearchScope::Heap => RegionFilter::pathname(r"^\[heap\]$"),
+            SearchScope::Both => RegionFilter::pathname(r"^\[(heap|stack(:\d+)?)\]$"),
+            SearchScope::All => RegionFilter {
+                writable: Flag::Set,
+                ..RegionFilter::default()
+            },
+            SearchScope::Custom => custom.clone(),
         }
     }
 }
+
+#[test]
+fn test_search_scope() {
+    let region = |pathname: &str| memscan::memory_source::MemoryRegion {
+        start: 0x1000,
+        end: 0x2000,
+        readable: true,
+        writable: true,
+        executable: false,
+        shared: false,
+        pathname: Some(pathname.to_string()),
+        offset: 0,
+    };
+    let maps = || {
+        [
+            "[stack]",
+            "[stack:1234]",
+            "[heap]",
+            "/usr/lib/libc.so.6",
+            "[stack:x]",
+        ]
+        .map(region)
+        .to_vec()
+    };
+    let selected = |scope: SearchScope| {
+        let matcher = scope.filter(&RegionFilter::default()).compile();
+        let regions = matcher
+            .unwrap_or_else(|err| panic!("{}", err))
+            .select(0, maps());
+        regions
+            .into_iter()
+            .filter_map(|region| region.pathname)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(selected(SearchScope::Stack), ["[stack]", "[stack:1234]"]);
+    assert_eq!(selected(SearchScope::Heap), ["[heap]"]);
+    assert_eq!(
+        selected(SearchScope::Both),
+        ["[stack]", "[stack:1234]", "[heap]"]
+    );
+}
@@ -0,0 +1,353 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use memscan::{
+    address_set::AddressSet,
+    varint::{read_bytes, read_i64, read_u64, write_bytes, write_i64, write_u64},
+};
+
+use crate::{
+    app_error::AppError,
+    data_types::DataType,
+    module_address::map_path,
+    process_memory,
+    process_picker::Process,
+    search::{SearchRegion, SearchResult},
+};
+
+const MAGIC: &[u8; 4] = b"MSSN";
+const VERSION: u32 = 2;
+
+/// One narrowing step of a scan, shown in the history and kept in sessions.
+#[derive(Clone, Debug)]
+pub struct ScanStep {
+    pub operator: String,
+    pub value: String,
+    pub data_type: DataType,
+}
+
+/// The state of a multi-step scan, saved so it can be resumed after the GUI
+/// or the target was restarted.
+///
+/// The file starts with the magic `MSSN` and a little endian version, all
+/// other integers are varints. It holds the pid and command name of every
+/// process with results, their file backed mappings, the scan history and the
+/// results with delta encoded addresses.
+pub struct Session {
+    /// `(pid, command name)` of the processes the results were taken from
+    pub processes: Vec<(i32, String)>,
+    /// File backed mappings of those processes, used to rebase results
+    pub regions: Vec<SearchRegion>,
+    pub history: Vec<ScanStep>,
+    pub results: Vec<SearchResult>,
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_string(writer: &mut impl Write, string: &str) -> io::Result<()> {
+    write_bytes(writer, string.as_bytes())
+}
+
+fn read_string(reader: &mut impl Read) -> io::Result<String> {
+    String::from_utf8(read_bytes(reader)?).map_err(|_| invalid_data("string is not UTF-8"))
+}
+
+fn write_data_type(writer: &mut impl Write, data_type: DataType) -> io::Result<()> {
+    write_string(writer, &serde_json::to_string(&data_type)?)
+}
+
+fn read_data_type(reader: &mut impl Read) -> io::Result<DataType> {
+    Ok(serde_json::from_str(&read_string(reader)?)?)
+}
+
+/// Pids are signed, pseudo processes like snapshots and remote targets have
+/// negative ones.
+fn write_pid(writer: &mut impl Write, pid: i32) -> io::Result<()> {
+    write_i64(writer, pid.into())
+}
+
+fn read_pid(reader: &mut impl Read) -> io::Result<i32> {
+    i32::try_from(read_i64(reader)?).map_err(|_| invalid_data("pid out of range"))
+}
+
+/// File backed mappings of `pid`, empty if the process is gone.
+fn file_regions(pid: i32) -> Vec<SearchRegion> {
+    let Ok(maps) = process_memory::regions(pid) else {
+        return vec![];
+    };
+    maps.iter()
+        .filter_map(|map| {
+            Some(SearchRegion {
+                pid,
+                start: map.start,
+                end: map.end,
+                pathname: Some(map_path(map)?),
+                offset: map.offset,
+            })
+        })
+        .collect()
+}
+
+impl Session {
+    /// Collects the session of `results`, `processes` provides the command
+    /// names of their pids.
+    pub fn capture(processes: &[Process], history: &[ScanStep], results: &[SearchResult]) -> Self {
+        let mut pids: Vec<i32> = results.iter().map(|result| result.pid).collect();
+        pids.sort_unstable();
+        pids.dedup();
+
+        let processes = pids
+            .iter()
+            .map(|&pid| {
+                let cmd = processes
+                    .iter()
+                    .find(|process| process.pid == pid)
+                    .map(|process| process.cmd.clone())
+                    .unwrap_or_default();
+                (pid, cmd)
+            })
+            .collect();
+        let regions = pids.iter().flat_map(|&pid| file_regions(pid)).collect();
+        let results = results.to_vec();
+
+        Self {
+            processes,
+            regions,
+            history: history.to_vec(),
+            results,
+        }
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+
+        write_u64(writer, self.processes.len() as u64)?;
+        for (pid, cmd) in &self.processes {
+            write_pid(writer, *pid)?;
+            write_string(writer, cmd)?;
+        }
+
+        write_u64(writer, self.regions.len() as u64)?;
+        for region in &self.regions {
+            write_pid(writer, region.pid)?;
+            write_u64(writer, region.start)?;
+            write_u64(writer, region.end - region.start)?;
+            write_u64(writer, region.offset)?;
+            write_string(writer, region.pathname.as_deref().unwrap_or_default())?;
+        }
+
+        write_u64(writer, self.history.len() as u64)?;
+        for step in &self.history {
+            write_string(writer, &step.operator)?;
+            write_string(writer, &step.value)?;
+            write_data_type(writer, step.data_type)?;
+        }
+
+        write_u64(writer, self.results.len() as u64)?;
+        for result in &self.results {
+            write_pid(writer, result.pid)?;
+            write_data_type(writer, result.data_type)?;
+            write_u64(writer, result.pointers.len() as u64)?;
+            let mut last = 0u64;
+            for pointer in &result.pointers {
+                write_i64(writer, pointer.wrapping_sub(last) as i64)?;
+                last = pointer;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid_data("not a session file"));
+        }
+        let version = u32::from_le_bytes(header[4..].try_into().unwrap());
+        if version != VERSION {
+            return Err(invalid_data(&format!(
+                "unsupported session version {}",
+                version
+            )));
+        }
+
+        let mut processes = vec![];
+        for _ in 0..read_u64(reader)? {
+            processes.push((read_pid(reader)?, read_string(reader)?));
+        }
+
+        let mut regions = vec![];
+        for _ in 0..read_u64(reader)? {
+            let pid = read_pid(reader)?;
+            let start = read_u64(reader)?;
+            let end = start
+                .checked_add(read_u64(reader)?)
+                .ok_or_else(|| invalid_data("region out of range"))?;
+            let offset = read_u64(reader)?;
+            let pathname = read_string(reader)?;
+            regions.push(SearchRegion {
+                pid,
+                start,
+                end,
+                pathname: (!pathname.is_empty()).then_some(pathname),
+                offset,
+            });
+        }
+
+        let mut history = vec![];
+        for _ in 0..read_u64(reader)? {
+            history.push(ScanStep {
+                operator: read_string(reader)?,
+                value: read_string(reader)?,
+                data_type: read_data_type(reader)?,
+            });
+        }
+
+        let mut results = vec![];
+        for _ in 0..read_u64(reader)? {
+            let pid = read_pid(reader)?;
+            let data_type = read_data_type(reader)?;
+            let mut pointers = vec![];
+            let mut last = 0u64;
+            for _ in 0..read_u64(reader)? {
+                last = last.wrapping_add(read_i64(reader)? as u64);
+                pointers.push(last);
+            }
+            let pointers = pointers.into_iter().collect();
+            results.push(SearchResult {
+                pid,
+                data_type,
+                pointers,
+            });
+        }
+
+        Ok(Self {
+            processes,
+            regions,
+            history,
+            results,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), AppError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, AppError> {
+        Ok(Self::read_from(&mut BufReader::new(File::open(path)?))?)
+    }
+
+    /// Maps the results onto `running` processes. Results of a process that
+    /// still runs under its pid are kept, those of a restarted process are
+    /// rebased through the file they lie in and dropped if they lie outside
+    /// of mapped files. Returns the results and the number of dropped ones.
+    pub fn resume(self, running: &[Process]) -> (Vec<SearchResult>, usize) {
+        let mut dropped = 0;
+        let mut results = vec![];
+        for result in self.results {
+            let cmd = self
+                .processes
+                .iter()
+                .find(|(pid, _)| *pid == result.pid)
+                .map_or("", |(_, cmd)| cmd.as_str());
+            if running
+                .iter()
+                .any(|process| process.pid == result.pid && process.cmd == cmd)
+            {
+                results.push(result);
+                continue;
+            }
+
+            /* Prefer a selected process of the same name */
+            let Some(process) = running
+                .iter()
+                .filter(|process| process.cmd == cmd)
+                .max_by_key(|process| process.selected)
+            else {
+                println!("[WARN]: {} ({}) is not running", cmd, result.pid);
+                results.push(result);
+                continue;
+            };
+            let Ok(maps) = process_memory::regions(process.pid) else {
+                dropped += result.pointers.len();
+                continue;
+            };
+
+            let pointers: AddressSet = result
+                .pointers
+                .iter()
+                .filter_map(|pointer| {
+                    let module =
+                        SearchRegion::module_address_in(&self.regions, result.pid, pointer)?;
+                    module.resolve(&maps)
+                })
+                .collect();
+            dropped += result.pointers.len() - pointers.len();
+            if !pointers.is_empty() {
+                results.push(SearchResult {
+                    pid: process.pid,
+                    data_type: result.data_type,
+                    pointers,
+                });
+            }
+        }
+        (results, dropped)
+    }
+}
+
+#[test]
+fn test_session() {
+    let session = Session {
+        processes: vec![(-2, "snapshot".to_string()), (1234, "game".to_string())],
+        regions: vec![SearchRegion {
+            pid: -2,
+            start: 0x1000,
+            end: 0x3000,
+            pathname: Some("/opt/game/game".to_string()),
+            offset: 0x2000,
+        }],
+        history: vec![ScanStep {
+            operator: "=".to_string(),
+            value: "100".to_string(),
+            data_type: DataType::I32,
+        }],
+        results: vec![SearchResult {
+            pid: -2,
+            data_type: DataType::I32,
+            pointers: [0x1010, 0x1008, 0x2000].into_iter().collect(),
+        }],
+    };
+
+    let mut buffer = vec![];
+    session.write_to(&mut buffer).unwrap();
+    let loaded = Session::read_from(&mut buffer.as_slice()).unwrap();
+    assert_eq!(loaded.processes, session.processes);
+    let region = &loaded.regions[0];
+    assert_eq!(
+        (region.pid, region.start, region.end, region.offset),
+        (-2, 0x1000, 0x3000, 0x2000)
+    );
+    assert_eq!(region.pathname.as_deref(), Some("/opt/game/game"));
+    assert_eq!(loaded.history[0].value, "100");
+    assert_eq!(loaded.history[0].data_type, DataType::I32);
+    let result = &loaded.results[0];
+    assert_eq!((result.pid, result.data_type), (-2, DataType::I32));
+    assert!(
+        result
+            .pointers
+            .iter()
+            .eq(session.results[0].pointers.iter())
+    );
+
+    buffer[4] = 1;
+    assert!(Session::read_from(&mut buffer.as_slice()).is_err());
+}
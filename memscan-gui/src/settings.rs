@@ -1,4 +1,7 @@
-use crate::{data_types::DataType, endian::Endianness, search_scope::SearchScope};
+use crate::{
+    data_types::DataType, endian::Endianness, region_filter::RegionPreset,
+    search_scope::SearchScope,
+};
 
 
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -10,6 +13,16 @@ pub struct Settings {
     pub default_data_type: DataType,
     pub default_endianness: Endianness,
     pub search_buffer_size: usize,
+    /// Compress runs of zeros in process dumps
+    pub compress_dumps: bool,
+    /// Stop live processes with ptrace while they are scanned
+    pub stop_processes: bool,
+    /// Read swapped out pages, which swaps them back in
+    pub scan_swapped_pages: bool,
+    /// Compare only pages written since the last snapshot, using soft-dirty bits
+    pub track_dirty_pages: bool,
+    /// Named region filters for the custom search scope
+    pub region_presets: Vec<RegionPreset>,
 }
 
 impl Default for Settings {
@@ -20,6 +33,11 @@ impl Default for Settings {
             default_data_type: DataType::default(),
             default_endianness: Endianness::default(),
             search_buffer_size: 128 * 1024 * 1024,
+            compress_dumps: true,
+            stop_processes: false,
+            scan_swapped_pages: true,
+            track_dirty_pages: false,
+            region_presets: RegionPreset::defaults(),
         }
     }
 }
@@ -48,6 +66,13 @@ impl Settings {
                         self.search_buffer_size = parsed;
                     }
                 }
+                ui.checkbox(&mut self.compress_dumps, "Compress process dumps");
+                ui.checkbox(&mut self.stop_processes, "Stop processes while scanning")
+                    .on_hover_text("Attach with ptrace so values can't change during a scan");
+                ui.checkbox(&mut self.scan_swapped_pages, "Scan swapped out pages")
+                    .on_hover_text("Pages that were never written to are always skipped");
+                ui.checkbox(&mut self.track_dirty_pages, "Rescan only dirty pages")
+                    .on_hover_text("Clears the soft-dirty bits of snapshotted processes");
             });
     }
 }
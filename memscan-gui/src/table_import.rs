@@ -0,0 +1,363 @@
+use std::{fmt::Display, fs, path::Path};
+
+use memscan::memory_source::MemoryRegion;
+
+use crate::{
+    app_error::AppError,
+    cheat_table::{CheatEntry, CheatTable, TableLocation},
+    data_types::DataType,
+    module_address::ModuleAddress,
+};
+
+fn import_error(message: impl Display) -> AppError {
+    AppError::Io(format!("Import failed: {}", message))
+}
+
+fn parse_hex(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let digits = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    u64::from_str_radix(digits, 16).ok()
+}
+
+/// Reads a cheat table of another tool, detected from its content: Cheat
+/// Engine `.CT` XML, a GameConqueror cheat list or the output of scanmem's
+/// `list` command. `maps` are the regions of the process the table is for,
+/// see `import_scanmem_list`.
+pub fn import_file(path: impl AsRef<Path>, maps: &[MemoryRegion]) -> Result<CheatTable, AppError> {
+    let content = fs::read_to_string(path)?;
+    let trimmed = content.trim_start();
+    if trimmed.starts_with('<') {
+        import_cheat_engine(&content)
+    } else if trimmed.starts_with('{') {
+        import_gameconqueror(&content)
+    } else {
+        import_scanmem_list(&content, maps)
+    }
+}
+
+/// Maps a Cheat Engine `VariableType`, other types like strings and byte
+/// arrays are not supported.
+fn cheat_engine_type(variable_type: &str, signed: bool) -> Option<DataType> {
+    Some(match (variable_type, signed) {
+        ("Byte", false) => DataType::U8,
+        ("Byte", true) => DataType::I8,
+        ("2 Bytes", false) => DataType::U16,
+        ("2 Bytes", true) => DataType::I16,
+        ("4 Bytes", false) => DataType::U32,
+        ("4 Bytes", true) => DataType::I32,
+        ("8 Bytes", false) => DataType::U64,
+        ("8 Bytes", true) => DataType::I64,
+        ("Float", _) => DataType::F32,
+        ("Double", _) => DataType::F64,
+        _ => return None,
+    })
+}
+
+/// Turns `"game.exe"+1A2B0` and the innermost-first offsets of a Cheat
+/// Engine entry into an address expression, or a plain address.
+fn cheat_engine_location(address: &str, offsets: &[&str]) -> Option<TableLocation> {
+    let address = address.replace('"', "");
+    let address = address.trim();
+    if offsets.is_empty()
+        && let Some(address) = parse_hex(address)
+    {
+        return Some(TableLocation::Address(address));
+    }
+
+    let base = match address.split_once('+') {
+        Some((module, offset)) => format!("{}+0x{:X}", module.trim(), parse_hex(offset)?),
+        None => match parse_hex(address) {
+            Some(address) => format!("0x{:X}", address),
+            None => address.to_string(),
+        },
+    };
+    let expression = offsets.iter().rev().try_fold(base, |expr, offset| {
+        Some(format!("[{}]+0x{:X}", expr, parse_hex(offset)?))
+    })?;
+    Some(TableLocation::Expression(expression))
+}
+
+fn child_text<'a>(node: roxmltree::Node<'a, 'a>, name: &str) -> Option<&'a str> {
+    node.children()
+        .find(|child| child.has_tag_name(name))
+        .and_then(|child| child.text())
+        .map(str::trim)
+}
+
+pub fn import_cheat_engine(xml: &str) -> Result<CheatTable, AppError> {
+    let document = roxmltree::Document::parse(xml).map_err(import_error)?;
+    let root = document.root_element();
+    if !root.has_tag_name("CheatTable") {
+        return Err(import_error("not a Cheat Engine table"));
+    }
+
+    let mut entries = vec![];
+    for node in root
+        .descendants()
+        .filter(|node| node.has_tag_name("CheatEntry"))
+    {
+        /* Groups have neither an address nor a type */
+        let (Some(address), Some(variable_type)) = (
+            child_text(node, "Address"),
+            child_text(node, "VariableType"),
+        ) else {
+            continue;
+        };
+        let signed = child_text(node, "ShowAsSigned") == Some("1");
+        let Some(data_type) = cheat_engine_type(variable_type, signed) else {
+            println!("[WARN]: Skipping unsupported type {}", variable_type);
+            continue;
+        };
+        let offsets: Vec<&str> = node
+            .children()
+            .filter(|child| child.has_tag_name("Offsets"))
+            .flat_map(|offsets| offsets.children())
+            .filter(|child| child.has_tag_name("Offset"))
+            .filter_map(|offset| offset.text())
+            .collect();
+        let Some(location) = cheat_engine_location(address, &offsets) else {
+            println!("[WARN]: Skipping unsupported address {}", address);
+            continue;
+        };
+
+        entries.push(CheatEntry {
+            description: child_text(node, "Description")
+                .unwrap_or_default()
+                .trim_matches('"')
+                .to_string(),
+            data_type,
+            location,
+            frozen: false,
+            value: None,
+        });
+    }
+
+    Ok(CheatTable::new(String::new(), entries))
+}
+
+fn scanmem_type(name: &str) -> Option<DataType> {
+    Some(match name {
+        "int8" | "I8" => DataType::I8,
+        "int16" | "I16" => DataType::I16,
+        "int32" | "int" | "I32" => DataType::I32,
+        "int64" | "I64" => DataType::I64,
+        "float32" | "float" | "F32" => DataType::F32,
+        "float64" | "F64" => DataType::F64,
+        _ => return None,
+    })
+}
+
+/// GameConqueror saves `{"cheat_list": [[locked, description, address, type,
+/// value, valid], ...]}` with hexadecimal addresses.
+pub fn import_gameconqueror(json: &str) -> Result<CheatTable, AppError> {
+    let document: serde_json::Value = serde_json::from_str(json).map_err(import_error)?;
+    let rows = document
+        .get("cheat_list")
+        .and_then(|list| list.as_array())
+        .ok_or_else(|| import_error("not a GameConqueror cheat list"))?;
+
+    let mut entries = vec![];
+    for row in rows {
+        let field = |index: usize| row.get(index).and_then(|field| field.as_str());
+        let (Some(address), Some(type_name)) = (field(2).and_then(parse_hex), field(3)) else {
+            return Err(import_error("malformed cheat list row"));
+        };
+        let Some(data_type) = scanmem_type(type_name) else {
+            println!("[WARN]: Skipping unsupported type {}", type_name);
+            continue;
+        };
+        let frozen = row.get(0).and_then(|field| field.as_bool()) == Some(true);
+
+        entries.push(CheatEntry {
+            description: field(1).unwrap_or_default().to_string(),
+            data_type,
+            location: TableLocation::Address(address),
+            frozen,
+            value: field(4).map(str::to_string),
+        });
+    }
+
+    Ok(CheatTable::new(String::new(), entries))
+}
+
+/// Parses the lines printed by scanmem's `list` command, e.g.
+/// `[ 0]     7f4b5c2a1234,  1 +    12a234,  heap, 100, [I32 I16 ]`.
+/// The widest integer type of each match is used.
+///
+/// Matches in `exe` and `code` regions keep their offset from the start of
+/// the file as a `ModuleAddress`. scanmem doesn't print the file, so it is
+/// taken from the mapping of `maps` the address is in; matches elsewhere or
+/// without `maps` stay plain addresses.
+pub fn import_scanmem_list(text: &str, maps: &[MemoryRegion]) -> Result<CheatTable, AppError> {
+    let mut entries = vec![];
+    for line in text
+        .lines()
+        .filter(|line| line.trim_start().starts_with('['))
+    {
+        let Some((_, rest)) = line.split_once(']') else {
+            continue;
+        };
+        let fields: Vec<&str> = rest.split(',').map(str::trim).collect();
+        let (Some(address), Some(types)) = (
+            fields.first().and_then(|field| parse_hex(field)),
+            fields.last(),
+        ) else {
+            continue;
+        };
+        let data_type = ["I64", "I32", "I16", "I8", "F64", "F32"]
+            .into_iter()
+            .find(|name| {
+                types
+                    .split_whitespace()
+                    .any(|field| field.trim_matches(['[', ']']).starts_with(name))
+            })
+            .and_then(scanmem_type);
+        let Some(data_type) = data_type else {
+            continue;
+        };
+
+        /* Scanmem's offset is relative to where the region is loaded, the
+        file offset comes from the map the address lies in */
+        let module = matches!(fields.get(2), Some(&("exe" | "code")))
+            .then(|| ModuleAddress::from_maps(maps, address))
+            .flatten();
+
+        entries.push(CheatEntry {
+            description: String::new(),
+            data_type,
+            location: module.map_or(TableLocation::Address(address), TableLocation::Module),
+            frozen: false,
+            value: None,
+        });
+    }
+
+    if entries.is_empty() {
+        return Err(import_error("no addresses found"));
+    }
+    Ok(CheatTable::new(String::new(), entries))
+}
+
+#[cfg(test)]
+fn assert_entry(entry: &CheatEntry, data_type: DataType, location: &str) {
+    assert_eq!(entry.data_type, data_type);
+    assert_eq!(entry.location.to_string(), location);
+}
+
+#[test]
+fn test_import_cheat_engine() {
+    let table = import_cheat_engine(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<CheatTable CheatEngineTableVersion="45">
+  <CheatEntries>
+    <CheatEntry>
+      <ID>0</ID>
+      <Description>"Player"</Description>
+      <GroupHeader>1</GroupHeader>
+      <CheatEntries>
+        <CheatEntry>
+          <ID>1</ID>
+          <Description>"Health"</Description>
+          <ShowAsSigned>1</ShowAsSigned>
+          <VariableType>4 Bytes</VariableType>
+          <Address>"game.exe"+1A2B0</Address>
+          <Offsets>
+            <Offset>40</Offset>
+            <Offset>18</Offset>
+          </Offsets>
+        </CheatEntry>
+      </CheatEntries>
+    </CheatEntry>
+    <CheatEntry>
+      <ID>2</ID>
+      <Description>"Speed"</Description>
+      <VariableType>Float</VariableType>
+      <Address>7FF6A000</Address>
+    </CheatEntry>
+    <CheatEntry>
+      <ID>3</ID>
+      <Description>"Name"</Description>
+      <VariableType>String</VariableType>
+      <Address>7FF6A100</Address>
+    </CheatEntry>
+  </CheatEntries>
+</CheatTable>
+"#,
+    )
+    .unwrap_or_else(|err| panic!("{}", err));
+
+    assert_eq!(table.entries.len(), 2);
+    assert_eq!(table.entries[0].description, "Health");
+    assert_entry(
+        &table.entries[0],
+        DataType::I32,
+        "[[game.exe+0x1A2B0]+0x18]+0x40",
+    );
+    assert_eq!(table.entries[1].description, "Speed");
+    assert_entry(&table.entries[1], DataType::F32, "0x7ff6a000");
+    assert!(import_cheat_engine("<Table/>").is_err());
+}
+
+#[test]
+fn test_import_gameconqueror() {
+    let table = import_gameconqueror(
+        r#"{"cheat_list": [
+            [true, "Health", "7f4b5c2a1234", "int32", "100", true],
+            [false, "Name", "7f4b5c2a2000", "string", "abc", true],
+            [false, "Speed", "0x7f4b5c2a3000", "float64", "1.5", true]
+        ]}"#,
+    )
+    .unwrap_or_else(|err| panic!("{}", err));
+
+    assert_eq!(table.entries.len(), 2);
+    assert!(table.entries[0].frozen);
+    assert_eq!(table.entries[0].value.as_deref(), Some("100"));
+    assert_entry(&table.entries[0], DataType::I32, "0x7f4b5c2a1234");
+    assert!(!table.entries[1].frozen);
+    assert_entry(&table.entries[1], DataType::F64, "0x7f4b5c2a3000");
+    assert!(import_gameconqueror(r#"{"cheat_list": [[false]]}"#).is_err());
+}
+
+#[test]
+fn test_import_scanmem_list() {
+    let list = "\
+> list
+[ 0]     55d0c0a01234,  0 +      1234,   exe, 100, [I32 I16 ]
+[ 1]     7f4b5c2a1234,  3 +     2a234,  heap, 7, [I64 I32 I16 I8 ]
+[ 2]     7ffd00001000,  5 +      1000, stack, 1.5, [F32 ]
+";
+    let maps = vec![MemoryRegion {
+        start: 0x55d0c0a00000,
+        end: 0x55d0c0a02000,
+        readable: true,
+        writable: true,
+        executable: false,
+        shared: false,
+        offset: 0,
+        pathname: Some("/opt/game/game".to_string()),
+    }];
+    let table = import_scanmem_list(list, &maps).unwrap_or_else(|err| panic!("{}", err));
+
+    assert_eq!(table.entries.len(), 3);
+    assert!(matches!(
+        &table.entries[0].location,
+        TableLocation::Module(module) if module.path == "/opt/game/game" && module.offset == 0x1234
+    ));
+    assert_entry(&table.entries[0], DataType::I32, "game@0x1234");
+    assert_entry(&table.entries[1], DataType::I64, "0x7f4b5c2a1234");
+    assert_entry(&table.entries[2], DataType::F32, "0x7ffd00001000");
+
+    /* Regions past the first page of the file are stored by file offset */
+    let maps = vec![MemoryRegion {
+        offset: 0x3000,
+        ..maps[0].clone()
+    }];
+    let table = import_scanmem_list(list, &maps).unwrap_or_else(|err| panic!("{}", err));
+    assert_entry(&table.entries[0], DataType::I32, "game@0x4234");
+
+    let table = import_scanmem_list(list, &[]).unwrap_or_else(|err| panic!("{}", err));
+    assert_entry(&table.entries[0], DataType::I32, "0x55d0c0a01234");
+    assert!(import_scanmem_list("> list\n", &maps).is_err());
+}
@@ -0,0 +1,201 @@
+use crate::varint::{read_u64, write_u64};
+
+/// Addresses per block, random access decodes at most this many deltas.
+const BLOCK_LEN: usize = 128;
+
+/// Sorted, distinct addresses such as the hits of a scan, stored as varint
+/// deltas. Densely packed hits take one byte each instead of eight.
+///
+/// The addresses are split into blocks that start with a full address, so
+/// indexing and `contains` only decode a single block.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AddressSet {
+    /// First address of every block and the offset of its deltas
+    blocks: Vec<(u64, usize)>,
+    deltas: Vec<u8>,
+    len: usize,
+    last: u64,
+}
+
+impl AddressSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Bytes used by the encoded addresses.
+    pub fn size_in_bytes(&self) -> usize {
+        self.deltas.len() + self.blocks.len() * size_of::<(u64, usize)>()
+    }
+
+    /// Appends an address, which has to be above all others. Pushing the
+    /// last address again does nothing.
+    pub fn push(&mut self, address: u64) {
+        if !self.is_empty() && address == self.last {
+            return;
+        }
+        assert!(
+            self.is_empty() || address > self.last,
+            "addresses have to be pushed in ascending order"
+        );
+        if self.len.is_multiple_of(BLOCK_LEN) {
+            self.blocks.push((address, self.deltas.len()));
+        } else {
+            write_u64(&mut self.deltas, address - self.last).unwrap();
+        }
+        self.len += 1;
+        self.last = address;
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        self.iter_from(0)
+    }
+
+    /// Iterates the addresses starting with the one at `index`, for paging
+    /// through large results.
+    pub fn iter_from(&self, index: usize) -> Iter<'_> {
+        let index = index.min(self.len);
+        let mut iter = Iter {
+            set: self,
+            deltas: &[],
+            index: index - index % BLOCK_LEN,
+            current: 0,
+        };
+        for _ in 0..index % BLOCK_LEN {
+            iter.next();
+        }
+        iter
+    }
+
+    pub fn get(&self, index: usize) -> Option<u64> {
+        self.iter_from(index).next()
+    }
+
+    pub fn contains(&self, address: u64) -> bool {
+        let block = self.blocks.partition_point(|&(first, _)| first <= address);
+        if block == 0 {
+            return false;
+        }
+        self.iter_from((block - 1) * BLOCK_LEN)
+            .take(BLOCK_LEN)
+            .take_while(|&other| other <= address)
+            .any(|other| other == address)
+    }
+
+    /// Addresses that are in both sets.
+    pub fn intersect(&self, other: &Self) -> Self {
+        let mut result = Self::new();
+        let mut others = other.iter().peekable();
+        for address in self {
+            while others.next_if(|&other| other < address).is_some() {}
+            if others.peek() == Some(&address) {
+                result.push(address);
+            }
+        }
+        result
+    }
+
+    /// Addresses of this set that are not in `other`.
+    pub fn subtract(&self, other: &Self) -> Self {
+        let mut result = Self::new();
+        let mut others = other.iter().peekable();
+        for address in self {
+            while others.next_if(|&other| other < address).is_some() {}
+            if others.peek() != Some(&address) {
+                result.push(address);
+            }
+        }
+        result
+    }
+}
+
+/// Collects addresses in any order, dropping duplicates.
+impl FromIterator<u64> for AddressSet {
+    fn from_iter<T: IntoIterator<Item = u64>>(iter: T) -> Self {
+        let mut addresses: Vec<u64> = iter.into_iter().collect();
+        addresses.sort_unstable();
+        let mut set = Self::new();
+        for address in addresses {
+            set.push(address);
+        }
+        set
+    }
+}
+
+impl<'a> IntoIterator for &'a AddressSet {
+    type Item = u64;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct Iter<'a> {
+    set: &'a AddressSet,
+    /// Remaining deltas of the current block
+    deltas: &'a [u8],
+    index: usize,
+    current: u64,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.set.len {
+            return None;
+        }
+        if self.index.is_multiple_of(BLOCK_LEN) {
+            let (first, offset) = self.set.blocks[self.index / BLOCK_LEN];
+            self.current = first;
+            self.deltas = &self.set.deltas[offset..];
+        } else {
+            self.current += read_u64(&mut self.deltas).unwrap();
+        }
+        self.index += 1;
+        Some(self.current)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.set.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+#[test]
+fn test_address_set() {
+    let addresses: Vec<u64> = (0..1000u64)
+        .map(|index| 0x5600_0000 + index * 4 + (index / 300) * 0x7f00_0000_0000)
+        .collect();
+    let set: AddressSet = addresses.iter().rev().chain(&addresses).copied().collect();
+
+    assert_eq!(set.len(), 1000);
+    assert_eq!(set.iter().collect::<Vec<_>>(), addresses);
+    assert!(set.size_in_bytes() < 1000 * 2);
+    for index in [0, 1, 127, 128, 129, 299, 300, 999] {
+        assert_eq!(set.get(index), Some(addresses[index]));
+        assert_eq!(set.iter_from(index).next(), Some(addresses[index]));
+        assert!(set.contains(addresses[index]));
+        assert!(!set.contains(addresses[index] + 1));
+    }
+    assert_eq!(set.get(1000), None);
+    assert_eq!(set.iter_from(990).len(), 10);
+    assert!(!set.contains(0));
+
+    let even: AddressSet = addresses.iter().step_by(2).copied().collect();
+    let odd: AddressSet = addresses.iter().skip(1).step_by(2).copied().collect();
+    assert_eq!(set.intersect(&even), even);
+    assert_eq!(set.subtract(&even), odd);
+    assert!(even.intersect(&odd).is_empty());
+    assert_eq!(even.subtract(&AddressSet::new()), even);
+}
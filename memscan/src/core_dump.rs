@@ -0,0 +1,389 @@
+use std::{fs::File, io, os::unix::fs::FileExt, path::Path};
+
+use crate::memory_source::{MemoryRegion, MemorySource};
+
+const ET_CORE: u16 = 4;
+const PT_LOAD: u32 = 1;
+const PT_NOTE: u32 = 4;
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+const NT_PRPSINFO: u32 = 3;
+const NT_FILE: u32 = 0x4649_4c45;
+const PHDR_SIZE: usize = 56;
+
+/// A `PT_LOAD` segment of the dump.
+#[derive(Clone, Debug, PartialEq)]
+struct Segment {
+    start: u64,
+    end: u64,
+    /// Bytes stored in the file, the rest of the segment reads as zeros
+    file_size: u64,
+    file_offset: u64,
+    flags: u32,
+}
+
+/// A file that was mapped into the dumped process, from the `NT_FILE` note.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MappedFile {
+    pub start: u64,
+    pub end: u64,
+    /// Offset of `start` into the file in bytes
+    pub offset: u64,
+    pub path: String,
+}
+
+/// A Linux ELF core file as written by the kernel or `gcore`, read lazily so
+/// dumps larger than memory can be searched.
+///
+/// Only 64 bit little endian dumps are supported. Segments that were not
+/// dumped, like read only file mappings, read as zeros.
+pub struct CoreDump {
+    file: File,
+    segments: Vec<Segment>,
+    files: Vec<MappedFile>,
+    pid: i32,
+    command: String,
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> io::Result<u16> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or_else(|| invalid_data("truncated core file"))
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> io::Result<u32> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or_else(|| invalid_data("truncated core file"))
+}
+
+fn u64_at(bytes: &[u8], offset: usize) -> io::Result<u64> {
+    bytes
+        .get(offset..offset + 8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or_else(|| invalid_data("truncated core file"))
+}
+
+fn read_bytes_at(file: &File, offset: u64, length: u64) -> io::Result<Vec<u8>> {
+    /* Don't trust sizes from the file with an allocation */
+    if length > file.metadata()?.len() {
+        return Err(invalid_data("truncated core file"));
+    }
+    let mut bytes = vec![0u8; length as usize];
+    file.read_exact_at(&mut bytes, offset)?;
+    Ok(bytes)
+}
+
+/// Calls `f` with the type and descriptor of every note in a `PT_NOTE`
+/// segment.
+fn for_each_note(notes: &[u8], mut f: impl FnMut(u32, &[u8]) -> io::Result<()>) -> io::Result<()> {
+    let align = |size: usize| size.next_multiple_of(4);
+    let mut offset = 0;
+    while offset + 12 <= notes.len() {
+        let name_size = u32_at(notes, offset)? as usize;
+        let desc_size = u32_at(notes, offset + 4)? as usize;
+        let note_type = u32_at(notes, offset + 8)?;
+        let desc_start = offset + 12 + align(name_size);
+        let desc = notes
+            .get(desc_start..desc_start + desc_size)
+            .ok_or_else(|| invalid_data("truncated note"))?;
+        f(note_type, desc)?;
+        offset = desc_start + align(desc_size);
+    }
+    Ok(())
+}
+
+/// `count, page_size, count * (start, end, page offset)` followed by the
+/// NUL terminated paths.
+fn parse_file_note(desc: &[u8]) -> io::Result<Vec<MappedFile>> {
+    let count = u64_at(desc, 0)? as usize;
+    let page_size = u64_at(desc, 8)?;
+    let names_start = count
+        .checked_mul(24)
+        .and_then(|size| size.checked_add(16))
+        .ok_or_else(|| invalid_data("invalid file note"))?;
+    let mut names = desc
+        .get(names_start..)
+        .ok_or_else(|| invalid_data("truncated file note"))?
+        .split(|&byte| byte == 0);
+
+    (0..count)
+        .map(|index| {
+            let entry = 16 + index * 24;
+            let name = names
+                .next()
+                .ok_or_else(|| invalid_data("truncated file note"))?;
+            Ok(MappedFile {
+                start: u64_at(desc, entry)?,
+                end: u64_at(desc, entry + 8)?,
+                offset: u64_at(desc, entry + 16)?.wrapping_mul(page_size),
+                path: String::from_utf8_lossy(name).into_owned(),
+            })
+        })
+        .collect()
+}
+
+impl CoreDump {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_file(File::open(path)?)
+    }
+
+    pub fn from_file(file: File) -> io::Result<Self> {
+        let header = read_bytes_at(&file, 0, 64)?;
+        if header[..4] != *b"\x7fELF" {
+            return Err(invalid_data("not an ELF file"));
+        }
+        if header[4] != 2 || header[5] != 1 {
+            return Err(invalid_data(
+                "only 64 bit little endian cores are supported",
+            ));
+        }
+        if u16_at(&header, 16)? != ET_CORE {
+            return Err(invalid_data("not a core file"));
+        }
+        let phdr_offset = u64_at(&header, 32)?;
+        let phdr_size = u16_at(&header, 54)? as usize;
+        let phdr_count = u16_at(&header, 56)? as u64;
+        if phdr_size < PHDR_SIZE {
+            return Err(invalid_data("invalid program header size"));
+        }
+        let phdrs = read_bytes_at(&file, phdr_offset, phdr_count * phdr_size as u64)?;
+
+        let mut dump = Self {
+            file,
+            segments: vec![],
+            files: vec![],
+            pid: 0,
+            command: String::new(),
+        };
+        for phdr in phdrs.chunks_exact(phdr_size) {
+            let file_offset = u64_at(phdr, 8)?;
+            let file_size = u64_at(phdr, 32)?;
+            match u32_at(phdr, 0)? {
+                PT_LOAD => {
+                    let start = u64_at(phdr, 16)?;
+                    dump.segments.push(Segment {
+                        start,
+                        end: start.saturating_add(u64_at(phdr, 40)?),
+                        file_size,
+                        file_offset,
+                        flags: u32_at(phdr, 4)?,
+                    });
+                }
+                PT_NOTE => {
+                    let notes = read_bytes_at(&dump.file, file_offset, file_size)?;
+                    for_each_note(&notes, |note_type, desc| {
+                        match note_type {
+                            NT_FILE => dump.files = parse_file_note(desc)?,
+                            NT_PRPSINFO => {
+                                dump.pid = u32_at(desc, 24)? as i32;
+                                let name = desc
+                                    .get(40..56)
+                                    .ok_or_else(|| invalid_data("truncated process info"))?;
+                                let length = name.iter().position(|&byte| byte == 0);
+                                dump.command =
+                                    String::from_utf8_lossy(&name[..length.unwrap_or(name.len())])
+                                        .into_owned();
+                            }
+                            _ => {}
+                        }
+                        Ok(())
+                    })?;
+                }
+                _ => {}
+            }
+        }
+        dump.segments.sort_by_key(|segment| segment.start);
+
+        Ok(dump)
+    }
+
+    /// Pid of the dumped process, 0 if the dump has no process info.
+    pub fn pid(&self) -> i32 {
+        self.pid
+    }
+
+    /// Command name of the dumped process.
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    pub fn files(&self) -> &[MappedFile] {
+        &self.files
+    }
+
+    fn segment_of(&self, address: u64) -> Option<&Segment> {
+        let index = self
+            .segments
+            .partition_point(|segment| segment.start <= address)
+            .checked_sub(1)?;
+        Some(&self.segments[index]).filter(|segment| address < segment.end)
+    }
+}
+
+impl MemorySource for CoreDump {
+    fn regions(&self) -> io::Result<Vec<MemoryRegion>> {
+        Ok(self
+            .segments
+            .iter()
+            .map(|segment| {
+                let file = self
+                    .files
+                    .iter()
+                    .find(|file| file.start <= segment.start && segment.start < file.end);
+                MemoryRegion {
+                    start: segment.start,
+                    end: segment.end,
+                    readable: segment.flags & PF_R != 0,
+                    writable: segment.flags & PF_W != 0,
+                    executable: segment.flags & PF_X != 0,
+                    shared: false,
+                    pathname: file.map(|file| file.path.clone()),
+                    offset: file.map_or(0, |file| file.offset + (segment.start - file.start)),
+                }
+            })
+            .collect())
+    }
+
+    fn read(&self, address: u64, buffer: &mut [u8]) -> io::Result<usize> {
+        let segment = self.segment_of(address).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("address {:#x} is not in the dump", address),
+            )
+        })?;
+        let length = buffer.len().min((segment.end - address) as usize);
+        let skip = address - segment.start;
+        let stored = segment.file_size.saturating_sub(skip).min(length as u64) as usize;
+
+        self.file
+            .read_exact_at(&mut buffer[..stored], segment.file_offset + skip)?;
+        buffer[stored..length].fill(0);
+        Ok(length)
+    }
+}
+
+/// A core with the given process info, file note and `(start, flags, bytes,
+/// memory size)` segments.
+#[cfg(test)]
+fn build_core(
+    pid: i32,
+    command: &str,
+    files: &[(u64, u64, u64, &str)],
+    segments: &[(u64, u32, &[u8], u64)],
+) -> Vec<u8> {
+    fn note(notes: &mut Vec<u8>, note_type: u32, desc: &[u8]) {
+        notes.extend(5u32.to_le_bytes());
+        notes.extend((desc.len() as u32).to_le_bytes());
+        notes.extend(note_type.to_le_bytes());
+        notes.extend(b"CORE\0\0\0\0");
+        notes.extend(desc);
+        notes.resize(notes.len().next_multiple_of(4), 0);
+    }
+
+    let mut prpsinfo = vec![0u8; 136];
+    prpsinfo[24..28].copy_from_slice(&pid.to_le_bytes());
+    prpsinfo[40..40 + command.len()].copy_from_slice(command.as_bytes());
+    let mut file_note = vec![];
+    file_note.extend((files.len() as u64).to_le_bytes());
+    file_note.extend(4096u64.to_le_bytes());
+    for (start, end, page, _) in files {
+        for value in [start, end, page] {
+            file_note.extend(value.to_le_bytes());
+        }
+    }
+    for (_, _, _, path) in files {
+        file_note.extend(path.as_bytes());
+        file_note.push(0);
+    }
+    let mut notes = vec![];
+    note(&mut notes, NT_PRPSINFO, &prpsinfo);
+    note(&mut notes, NT_FILE, &file_note);
+
+    let phdr_count = segments.len() + 1;
+    let mut data_offset = (64 + phdr_count * PHDR_SIZE) as u64;
+    let mut core = vec![0u8; 64];
+    core[..6].copy_from_slice(b"\x7fELF\x02\x01");
+    core[16..18].copy_from_slice(&ET_CORE.to_le_bytes());
+    core[32..40].copy_from_slice(&64u64.to_le_bytes());
+    core[54..56].copy_from_slice(&(PHDR_SIZE as u16).to_le_bytes());
+    core[56..58].copy_from_slice(&(phdr_count as u16).to_le_bytes());
+
+    let mut phdr =
+        |core: &mut Vec<u8>, kind: u32, flags: u32, start: u64, size: u64, memory: u64| {
+            for value in [kind, flags] {
+                core.extend(value.to_le_bytes());
+            }
+            for value in [data_offset, start, 0, size, memory, 1] {
+                core.extend(value.to_le_bytes());
+            }
+            data_offset += size;
+        };
+    phdr(&mut core, PT_NOTE, 0, 0, notes.len() as u64, 0);
+    for (start, flags, bytes, memory) in segments {
+        phdr(
+            &mut core,
+            PT_LOAD,
+            *flags,
+            *start,
+            bytes.len() as u64,
+            *memory,
+        );
+    }
+    core.extend(notes);
+    for (_, _, bytes, _) in segments {
+        core.extend(*bytes);
+    }
+    core
+}
+
+#[test]
+fn test_core_dump() {
+    let core = build_core(
+        4242,
+        "game",
+        &[(0x40_0000, 0x40_2000, 1, "/opt/game/game")],
+        &[
+            (0x40_0000, PF_R | PF_X, &[], 0x2000),
+            (0x7f00_0000, PF_R | PF_W, &[1, 2, 3, 4], 0x1000),
+        ],
+    );
+    let path = std::env::temp_dir().join(format!("memscan-test-{}.core", std::process::id()));
+    std::fs::write(&path, core).unwrap();
+    let dump = CoreDump::open(&path);
+    std::fs::remove_file(&path).unwrap();
+    let dump = dump.unwrap();
+
+    assert_eq!(dump.pid(), 4242);
+    assert_eq!(dump.command(), "game");
+    assert_eq!(dump.files()[0].offset, 0x1000);
+    let regions = dump.regions().unwrap();
+    assert_eq!(
+        regions[0],
+        MemoryRegion {
+            start: 0x40_0000,
+            end: 0x40_2000,
+            readable: true,
+            writable: false,
+            executable: true,
+            shared: false,
+            pathname: Some("/opt/game/game".to_string()),
+            offset: 0x1000,
+        }
+    );
+    assert!(regions[1].writable && regions[1].pathname.is_none());
+
+    let mut buffer = [0xffu8; 8];
+    assert_eq!(dump.read(0x7f00_0002, &mut buffer).unwrap(), 8);
+    assert_eq!(buffer, [3, 4, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(dump.read(0x40_1ffc, &mut buffer).unwrap(), 4);
+    assert_eq!(buffer[..4], [0; 4]);
+    assert!(dump.read(0x7f00_1000, &mut buffer).is_err());
+}
@@ -0,0 +1,133 @@
+use std::io::{self, Write};
+
+/// One address of a search result or watch list, as written by `write_csv`
+/// and `write_json`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExportRecord {
+    pub pid: i32,
+    pub address: u64,
+    /// File the address is mapped from and the offset into that file
+    pub module: Option<(String, u64)>,
+    pub data_type: String,
+    /// Empty if the value could not be read
+    pub value: String,
+    pub description: String,
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn json_string(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len() + 2);
+    escaped.push('"');
+    for c in field.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Writes a header line followed by one line per record. Addresses and
+/// offsets are hexadecimal with a `0x` prefix, module and offset are empty
+/// for addresses outside of mapped files.
+pub fn write_csv(writer: &mut impl Write, records: &[ExportRecord]) -> io::Result<()> {
+    writeln!(writer, "pid,address,module,offset,type,value,description")?;
+    for record in records {
+        let (module, offset) = match &record.module {
+            Some((module, offset)) => (csv_field(module), format!("{:#x}", offset)),
+            None => (String::new(), String::new()),
+        };
+        writeln!(
+            writer,
+            "{},{:#x},{},{},{},{},{}",
+            record.pid,
+            record.address,
+            module,
+            offset,
+            csv_field(&record.data_type),
+            csv_field(&record.value),
+            csv_field(&record.description)
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes the records as a JSON array of objects with the same fields as the
+/// CSV columns. Numbers stay numbers, `module` and `offset` are `null` for
+/// addresses outside of mapped files.
+pub fn write_json(writer: &mut impl Write, records: &[ExportRecord]) -> io::Result<()> {
+    writeln!(writer, "[")?;
+    for (index, record) in records.iter().enumerate() {
+        let (module, offset) = match &record.module {
+            Some((module, offset)) => (json_string(module), offset.to_string()),
+            None => ("null".to_string(), "null".to_string()),
+        };
+        let separator = if index + 1 < records.len() { "," } else { "" };
+        writeln!(
+            writer,
+            "  {{\"pid\": {}, \"address\": {}, \"module\": {}, \"offset\": {}, \"type\": {}, \"value\": {}, \"description\": {}}}{}",
+            record.pid,
+            record.address,
+            module,
+            offset,
+            json_string(&record.data_type),
+            json_string(&record.value),
+            json_string(&record.description),
+            separator
+        )?;
+    }
+    writeln!(writer, "]")
+}
+
+#[test]
+fn test_export() {
+    let records = [
+        ExportRecord {
+            pid: 42,
+            address: 0x7f00_1000,
+            module: Some(("/usr/lib/libgame.so".to_string(), 0x1a2b0)),
+            data_type: "i32".to_string(),
+            value: "100".to_string(),
+            description: "Health, \"max\"".to_string(),
+        },
+        ExportRecord {
+            pid: 42,
+            address: 0x5600_0010,
+            data_type: "f32".to_string(),
+            ..Default::default()
+        },
+    ];
+
+    let mut csv = vec![];
+    write_csv(&mut csv, &records).unwrap();
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "pid,address,module,offset,type,value,description\n\
+         42,0x7f001000,/usr/lib/libgame.so,0x1a2b0,i32,100,\"Health, \"\"max\"\"\"\n\
+         42,0x56000010,,,f32,,\n"
+    );
+
+    let mut json = vec![];
+    write_json(&mut json, &records).unwrap();
+    assert_eq!(
+        String::from_utf8(json).unwrap(),
+        "[\n  {\"pid\": 42, \"address\": 2130710528, \"module\": \"/usr/lib/libgame.so\", \
+         \"offset\": 107184, \"type\": \"i32\", \"value\": \"100\", \
+         \"description\": \"Health, \\\"max\\\"\"},\n  \
+         {\"pid\": 42, \"address\": 1442840592, \"module\": null, \"offset\": null, \
+         \"type\": \"f32\", \"value\": \"\", \"description\": \"\"}\n]\n"
+    );
+}
@@ -0,0 +1,473 @@
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    sync::Mutex,
+};
+
+use crate::{
+    memory_source::{MemoryRegion, MemorySource, parse_maps},
+    remote::{Stream, connect_stream},
+};
+
+/// Packet size assumed if the stub doesn't report one.
+const DEFAULT_PACKET_SIZE: usize = 0x400;
+/// Bytes of a packet that aren't data, like `$`, the command and `#xx`.
+const PACKET_OVERHEAD: usize = 64;
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &[u8]) -> io::Result<Vec<u8>> {
+    hex.chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .filter(|pair| pair.len() == 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| invalid_data("invalid hex data"))
+        })
+        .collect()
+}
+
+fn parse_hex(hex: &[u8]) -> io::Result<u64> {
+    std::str::from_utf8(hex)
+        .ok()
+        .and_then(|hex| u64::from_str_radix(hex, 16).ok())
+        .ok_or_else(|| invalid_data("invalid hex number"))
+}
+
+/// Expands run length encoding, `x*n` repeats `x` another `n - 29` times.
+fn expand_runs(data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut expanded = Vec::with_capacity(data.len());
+    let mut bytes = data.iter();
+    while let Some(&byte) = bytes.next() {
+        if byte != b'*' {
+            expanded.push(byte);
+            continue;
+        }
+        let (Some(&last), Some(&count)) = (expanded.last(), bytes.next()) else {
+            return Err(invalid_data("invalid run length encoding"));
+        };
+        let repeats = count
+            .checked_sub(29)
+            .ok_or_else(|| invalid_data("invalid run"))?;
+        expanded.extend(std::iter::repeat_n(last, repeats as usize));
+    }
+    Ok(expanded)
+}
+
+/// Decodes binary data, where `}` escapes the following byte xor `0x20`.
+fn unescape(data: &[u8]) -> Vec<u8> {
+    let mut unescaped = Vec::with_capacity(data.len());
+    let mut bytes = data.iter();
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            b'}' => unescaped.extend(bytes.next().map(|byte| byte ^ 0x20)),
+            byte => unescaped.push(byte),
+        }
+    }
+    unescaped
+}
+
+/// Error replies look like `E01` or `E.message`, data never has odd length.
+fn is_error(reply: &[u8]) -> bool {
+    reply.first() == Some(&b'E') && (reply.len() % 2 == 1 || reply.get(1) == Some(&b'.'))
+}
+
+fn stub_error(reply: &[u8]) -> io::Error {
+    io::Error::other(format!(
+        "gdb stub replied {}",
+        String::from_utf8_lossy(reply)
+    ))
+}
+
+/// Value of the attribute `name` of an XML element.
+fn attribute<'a>(element: &'a str, name: &str) -> Option<&'a str> {
+    let value = element
+        .split_whitespace()
+        .find_map(|attribute| attribute.strip_prefix(name)?.strip_prefix('='))?;
+    let quote = value
+        .chars()
+        .next()
+        .filter(|&quote| quote == '"' || quote == '\'')?;
+    value[1..].split(quote).next()
+}
+
+/// Parses a `qXfer:memory-map` document like
+/// `<memory-map><memory type="ram" start="0x0" length="0x8000"/></memory-map>`.
+fn parse_memory_map(xml: &str) -> Vec<MemoryRegion> {
+    let number = |value: &str| match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    };
+    let mut regions: Vec<MemoryRegion> = xml
+        .split("<memory ")
+        .skip(1)
+        .filter_map(|element| {
+            let element = &element[..element.find('>')?];
+            let start = number(attribute(element, "start")?)?;
+            let length = number(attribute(element, "length")?)?;
+            Some(MemoryRegion {
+                start,
+                end: start.checked_add(length)?,
+                readable: true,
+                writable: attribute(element, "type") == Some("ram"),
+                executable: false,
+                shared: false,
+                pathname: None,
+                offset: 0,
+            })
+        })
+        .collect();
+    regions.sort_by_key(|region| region.start);
+    regions
+}
+
+/// A target behind a gdb stub, such as `gdbserver`, QEMU's `-s` or an
+/// emulator, accessed with the GDB remote serial protocol.
+///
+/// Memory is read and written with `m` and `M` packets. Regions come from the
+/// `qXfer:memory-map` document if the stub has one, else from the target's
+/// `/proc/<pid>/maps` through host I/O, which `gdbserver` supports.
+pub struct GdbRemote {
+    stream: Mutex<BufReader<Box<dyn Stream>>>,
+    packet_size: usize,
+    memory_map: bool,
+    pid: Option<i32>,
+}
+
+impl GdbRemote {
+    /// Connects to a stub at `host:port` or at `unix:<path>`.
+    pub fn connect(address: &str) -> io::Result<Self> {
+        Self::new(connect_stream(address)?)
+    }
+
+    pub fn new(stream: impl Read + Write + Send + 'static) -> io::Result<Self> {
+        let stream: Box<dyn Stream> = Box::new(stream);
+        let mut remote = Self {
+            stream: Mutex::new(BufReader::new(stream)),
+            packet_size: DEFAULT_PACKET_SIZE,
+            memory_map: false,
+            pid: None,
+        };
+
+        let features = remote.request(b"qSupported:multiprocess+")?;
+        for feature in features.split(|&byte| byte == b';') {
+            if let Some(size) = feature.strip_prefix(b"PacketSize=") {
+                remote.packet_size = (parse_hex(size)? as usize).max(PACKET_OVERHEAD * 2);
+            } else if feature == b"qXfer:memory-map:read+" {
+                remote.memory_map = true;
+            }
+        }
+
+        /* `QCp<pid>.<tid>` with multiprocess, `QC<tid>` without */
+        let thread = remote.request(b"qC")?;
+        remote.pid = thread.strip_prefix(b"QC").and_then(|thread| {
+            let thread = thread.strip_prefix(b"p").unwrap_or(thread);
+            let pid = thread.split(|&byte| byte == b'.').next()?;
+            parse_hex(pid).ok().and_then(|pid| i32::try_from(pid).ok())
+        });
+        Ok(remote)
+    }
+
+    /// The pid of the debugged process, if the stub reports one.
+    pub fn pid(&self) -> Option<i32> {
+        self.pid
+    }
+
+    /// Sends `packet` until the stub acknowledges it and returns the reply.
+    fn request(&self, packet: &[u8]) -> io::Result<Vec<u8>> {
+        let mut stream = self.stream.lock().unwrap();
+
+        let mut framed = Vec::with_capacity(packet.len() + 4);
+        framed.push(b'$');
+        framed.extend_from_slice(packet);
+        framed.extend_from_slice(format!("#{:02x}", checksum(packet)).as_bytes());
+        loop {
+            stream.get_mut().write_all(&framed)?;
+            stream.get_mut().flush()?;
+            match read_byte(&mut *stream)? {
+                b'+' => break,
+                b'-' => continue,
+                _ => return Err(invalid_data("expected an acknowledgement")),
+            }
+        }
+
+        loop {
+            /* Skip anything before the reply, like notifications */
+            stream.skip_until(b'$')?;
+            let mut data = vec![];
+            stream.read_until(b'#', &mut data)?;
+            if data.pop() != Some(b'#') {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+            }
+            let mut sum = [0u8; 2];
+            stream.read_exact(&mut sum)?;
+            if parse_hex(&sum)? as u8 != checksum(&data) {
+                stream.get_mut().write_all(b"-")?;
+                stream.get_mut().flush()?;
+                continue;
+            }
+            stream.get_mut().write_all(b"+")?;
+            stream.get_mut().flush()?;
+            return expand_runs(&data);
+        }
+    }
+
+    /// Reads a `qXfer` object, which arrives in parts of binary data.
+    fn read_object(&self, object: &str) -> io::Result<Vec<u8>> {
+        let length = self.packet_size - PACKET_OVERHEAD;
+        let mut content = vec![];
+        loop {
+            let request = format!("qXfer:{}:read::{:x},{:x}", object, content.len(), length);
+            let reply = self.request(request.as_bytes())?;
+            match reply.split_first() {
+                Some((b'm', data)) => content.extend(unescape(data)),
+                Some((b'l', data)) => {
+                    content.extend(unescape(data));
+                    return Ok(content);
+                }
+                _ => return Err(stub_error(&reply)),
+            }
+        }
+    }
+
+    /// Reads a file on the stub's machine with host I/O.
+    fn read_file(&self, path: &str) -> io::Result<Vec<u8>> {
+        /* Replies are `F<result>` or `F-1,<errno>` */
+        let result = |reply: Vec<u8>| -> io::Result<(u64, Vec<u8>)> {
+            let reply = reply.strip_prefix(b"F").ok_or_else(|| stub_error(&reply))?;
+            if reply.starts_with(b"-1") {
+                return Err(stub_error(reply));
+            }
+            let end = reply.iter().position(|&byte| byte == b';');
+            let value = parse_hex(&reply[..end.unwrap_or(reply.len())])?;
+            let data = end.map(|end| unescape(&reply[end + 1..]));
+            Ok((value, data.unwrap_or_default()))
+        };
+
+        let open = format!("vFile:open:{},0,0", to_hex(path.as_bytes()));
+        let (fd, _) = result(self.request(open.as_bytes())?)?;
+        let mut content = vec![];
+        let length = (self.packet_size - PACKET_OVERHEAD) / 2;
+        let read = loop {
+            let pread = format!("vFile:pread:{:x},{:x},{:x}", fd, length, content.len());
+            match self.request(pread.as_bytes()).and_then(result) {
+                Ok((0, _)) => break Ok(content),
+                Ok((_, data)) => content.extend(data),
+                Err(error) => break Err(error),
+            }
+        };
+        self.request(format!("vFile:close:{:x}", fd).as_bytes())?;
+        read
+    }
+}
+
+fn read_byte(reader: &mut impl Read) -> io::Result<u8> {
+    let mut byte = [0u8];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+impl MemorySource for GdbRemote {
+    fn regions(&self) -> io::Result<Vec<MemoryRegion>> {
+        if self.memory_map {
+            let xml = self.read_object("memory-map")?;
+            return Ok(parse_memory_map(&String::from_utf8_lossy(&xml)));
+        }
+        if let Some(pid) = self.pid
+            && let Ok(maps) = self.read_file(&format!("/proc/{}/maps", pid))
+        {
+            return Ok(parse_maps(&String::from_utf8_lossy(&maps)));
+        }
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the gdb stub reports no memory map, give an address range in the region filter",
+        ))
+    }
+
+    fn read(&self, address: u64, buffer: &mut [u8]) -> io::Result<usize> {
+        let mut read = 0;
+        for part in buffer.chunks_mut((self.packet_size - PACKET_OVERHEAD) / 2) {
+            let request = format!("m{:x},{:x}", address + read as u64, part.len());
+            let reply = self.request(request.as_bytes())?;
+            if is_error(&reply) {
+                if read == 0 {
+                    /* The stub only fails reads of memory it can't access */
+                    let error = stub_error(&reply);
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, error));
+                }
+                break;
+            }
+            let bytes = from_hex(&reply)?;
+            let length = bytes.len().min(part.len());
+            part[..length].copy_from_slice(&bytes[..length]);
+            read += length;
+            if length < part.len() {
+                break;
+            }
+        }
+        Ok(read)
+    }
+
+    fn write(&mut self, address: u64, data: &[u8]) -> io::Result<usize> {
+        let mut written = 0;
+        for part in data.chunks((self.packet_size - PACKET_OVERHEAD) / 2) {
+            let request = format!(
+                "M{:x},{:x}:{}",
+                address + written as u64,
+                part.len(),
+                to_hex(part)
+            );
+            let reply = self.request(request.as_bytes())?;
+            if reply != b"OK" {
+                if written == 0 {
+                    return Err(stub_error(&reply));
+                }
+                break;
+            }
+            written += part.len();
+        }
+        Ok(written)
+    }
+}
+
+/// Answers the packets of a `GdbRemote` from `memory` like a minimal stub,
+/// with a memory map and run length encoded replies.
+#[cfg(test)]
+fn serve_stub(
+    stream: std::os::unix::net::UnixStream,
+    mut memory: crate::memory_source::BufferSource,
+) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut send = |reply: &[u8]| -> io::Result<()> {
+        /* Compress runs of four or more, the shortest run with a printable count */
+        let mut encoded = vec![];
+        let mut index = 0;
+        while index < reply.len() {
+            let run = reply[index..]
+                .iter()
+                .take(97)
+                .take_while(|&&byte| byte == reply[index])
+                .count();
+            encoded.push(reply[index]);
+            if run >= 4 && ![b'#' - 29 + 1, b'$' - 29 + 1].contains(&(run as u8)) {
+                encoded.extend([b'*', run as u8 - 1 + 29]);
+                index += run;
+            } else {
+                index += 1;
+            }
+        }
+        write!(
+            writer,
+            "${}#{:02x}",
+            String::from_utf8_lossy(&encoded),
+            checksum(&encoded)
+        )?;
+        writer.flush()
+    };
+    let memory_map = "<memory-map><memory type=\"ram\" start=\"0x1000\" length=\"0x100\"/>\
+                      <memory type='rom' start='0x2000' length='0x10'/></memory-map>";
+
+    loop {
+        /* Acknowledgements are skipped, the client hanging up ends the stub */
+        reader.skip_until(b'$')?;
+        let mut packet = vec![];
+        reader.read_until(b'#', &mut packet)?;
+        if packet.pop() != Some(b'#') {
+            return Ok(());
+        }
+        let mut sum = [0u8; 2];
+        reader.read_exact(&mut sum)?;
+        assert_eq!(parse_hex(&sum)? as u8, checksum(&packet));
+        reader.get_mut().write_all(b"+")?;
+
+        let packet = String::from_utf8(packet).unwrap();
+        if packet.starts_with("qSupported") {
+            send(b"PacketSize=90;qXfer:memory-map:read+")?;
+        } else if packet == "qC" {
+            send(b"QCp2a.2a")?;
+        } else if let Some(range) = packet.strip_prefix("qXfer:memory-map:read::") {
+            let (offset, length) = range.split_once(',').unwrap();
+            let offset = (parse_hex(offset.as_bytes())? as usize).min(memory_map.len());
+            let length = parse_hex(length.as_bytes())? as usize;
+            let end = (offset + length).min(memory_map.len());
+            let kind = if end == memory_map.len() { "l" } else { "m" };
+            send(format!("{}{}", kind, &memory_map[offset..end]).as_bytes())?;
+        } else if let Some(range) = packet.strip_prefix('m') {
+            let (address, length) = range.split_once(',').unwrap();
+            let mut buffer = vec![0u8; parse_hex(length.as_bytes())? as usize];
+            match memory.read(parse_hex(address.as_bytes())?, &mut buffer) {
+                Ok(read) => send(to_hex(&buffer[..read]).as_bytes())?,
+                Err(_) => send(b"E14")?,
+            }
+        } else if let Some(range) = packet.strip_prefix('M') {
+            let (range, data) = range.split_once(':').unwrap();
+            let (address, _) = range.split_once(',').unwrap();
+            match memory.write(parse_hex(address.as_bytes())?, &from_hex(data.as_bytes())?) {
+                Ok(_) => send(b"OK")?,
+                Err(_) => send(b"E14")?,
+            }
+        } else {
+            send(b"")?;
+        }
+    }
+}
+
+#[test]
+fn test_gdb_remote() {
+    use crate::memory_source::BufferSource;
+
+    assert_eq!(expand_runs(b"0* 1").unwrap(), b"00001");
+    assert_eq!(unescape(b"a}\x03b"), b"a#b");
+    assert_eq!(
+        attribute("memory type='ram' start=\"0x10\"", "start"),
+        Some("0x10")
+    );
+
+    let mut memory = BufferSource::new();
+    let mut ram = vec![0u8; 0x100];
+    ram[0x80..0x84].copy_from_slice(&[1, 2, 3, 4]);
+    memory.map(0x1000, ram.clone());
+    memory.map(0x2000, vec![0xee; 0x10]);
+
+    let (client, server) = std::os::unix::net::UnixStream::pair().unwrap();
+    let stub = std::thread::spawn(move || serve_stub(server, memory));
+    let mut remote = GdbRemote::new(client).unwrap();
+    assert_eq!(remote.pid(), Some(0x2a));
+
+    let regions = remote.regions().unwrap();
+    assert_eq!(regions.len(), 2);
+    assert_eq!((regions[0].start, regions[0].end), (0x1000, 0x1100));
+    assert!(regions[0].writable && !regions[1].writable);
+    /* Stubs like QEMU on x86 have neither a memory map nor host I/O */
+    (remote.memory_map, remote.pid) = (false, None);
+    let error = remote.regions().unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+
+    /* Larger than a packet and running into unmapped memory */
+    let mut buffer = vec![0u8; 0x200];
+    assert_eq!(remote.read(0x1000, &mut buffer).unwrap(), 0x100);
+    assert_eq!(buffer[..0x100], ram);
+    assert!(remote.read(0x3000, &mut buffer).is_err());
+
+    let data: Vec<u8> = (0..0x50).collect();
+    assert_eq!(remote.write(0x1010, &data).unwrap(), 0x50);
+    assert_eq!(remote.read(0x1010, &mut buffer[..0x50]).unwrap(), 0x50);
+    assert_eq!(buffer[..0x50], data);
+
+    let found = remote.find_bytes(0x1000, 0x1100, &[1, 2, 3, 4], 4).unwrap();
+    assert_eq!(found.iter().collect::<Vec<_>>(), [0x1080]);
+
+    drop(remote);
+    stub.join().unwrap().unwrap();
+}
@@ -1,3 +1,14 @@
 #![allow(unsafe_op_in_unsafe_fn)]
 
+pub mod address_set;
+pub mod core_dump;
+pub mod export;
+pub mod gdb_remote;
+pub mod memory_source;
+pub mod pagemap;
+pub mod pointer_map_file;
+pub mod pointer_scan;
+pub mod remote;
 pub mod search;
+pub mod snapshot;
+pub mod varint;
@@ -0,0 +1,529 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    ops::Range,
+    os::unix::fs::FileExt,
+};
+
+use libc::{iovec, process_vm_readv, process_vm_writev};
+
+use crate::{
+    address_set::AddressSet,
+    pagemap,
+    search::packed::PackedSearch,
+    varint::{read_bytes, read_u64, write_bytes, write_u64},
+};
+
+const READABLE: u64 = 1;
+const WRITABLE: u64 = 2;
+const EXECUTABLE: u64 = 4;
+const SHARED: u64 = 8;
+
+/// A mapped range of a memory source.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MemoryRegion {
+    pub start: u64,
+    pub end: u64,
+    pub readable: bool,
+    pub writable: bool,
+    pub executable: bool,
+    /// Shared with other processes rather than copied on write
+    pub shared: bool,
+    /// File the region maps or a pseudo path like `[heap]`
+    pub pathname: Option<String>,
+    /// Offset of `start` into `pathname`
+    pub offset: u64,
+}
+
+impl MemoryRegion {
+    pub fn contains(&self, address: u64) -> bool {
+        self.start <= address && address < self.end
+    }
+}
+
+/// Memory that can be searched: a live process, a dump or a plain buffer.
+pub trait MemorySource {
+    /// The mapped regions, sorted by address.
+    fn regions(&self) -> io::Result<Vec<MemoryRegion>>;
+
+    /// Reads up to `buffer.len()` bytes at `address` and returns how many
+    /// were read, which is less if the read runs into unmapped memory.
+    fn read(&self, address: u64, buffer: &mut [u8]) -> io::Result<usize>;
+
+    /// Writes `data` at `address` and returns how many bytes were written.
+    /// Read only sources return `ErrorKind::Unsupported`.
+    fn write(&mut self, address: u64, data: &[u8]) -> io::Result<usize> {
+        let _ = (address, data);
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "memory source is read only",
+        ))
+    }
+
+    /// Whether reads are expensive round trips, so searches should rather
+    /// use `find_bytes`.
+    fn is_remote(&self) -> bool {
+        false
+    }
+
+    /// Addresses in `start..end` that hold `needle` and are a multiple of
+    /// `alignment`. Unreadable parts are skipped. Remote sources search on
+    /// their side, everything else reads the memory in chunks.
+    fn find_bytes(
+        &self,
+        start: u64,
+        end: u64,
+        needle: &[u8],
+        alignment: usize,
+    ) -> io::Result<AddressSet> {
+        const CHUNK: usize = 16 * 1024 * 1024;
+
+        let mut found = AddressSet::new();
+        if needle.is_empty() {
+            return Ok(found);
+        }
+        /* Chunks overlap so matches crossing a chunk border are found */
+        let overlap = needle.len() - 1;
+        let mut buffer = vec![0u8; CHUNK + overlap];
+        for chunk_start in (start..end).step_by(CHUNK) {
+            let length = ((end - chunk_start) as usize).min(CHUNK + overlap);
+            for part in read_pages(
+                self,
+                chunk_start,
+                &mut buffer[..length],
+                pagemap::page_size(),
+            )? {
+                for offset in PackedSearch::new(needle, &buffer[part.clone()]) {
+                    let offset = part.start + offset;
+                    let address = chunk_start + offset as u64;
+                    /* Matches starting inside the overlap belong to the next chunk */
+                    if offset < CHUNK && address.is_multiple_of(alignment.max(1) as u64) {
+                        found.push(address);
+                    }
+                }
+            }
+        }
+        Ok(found)
+    }
+}
+
+/// Reads `buffer.len()` bytes at `address` and returns the parts of `buffer`
+/// that were read. A short read or one failing on unreadable memory stops at
+/// an unreadable page, like a guard page or a region unmapped meanwhile, so
+/// the read resumes after it. Other errors are returned.
+pub fn read_pages(
+    source: &(impl MemorySource + ?Sized),
+    address: u64,
+    buffer: &mut [u8],
+    page_size: u64,
+) -> io::Result<Vec<Range<usize>>> {
+    let mut parts: Vec<Range<usize>> = vec![];
+    let mut offset = 0;
+    while offset < buffer.len() {
+        let read = match source.read(address + offset as u64, &mut buffer[offset..]) {
+            Ok(read) => read.min(buffer.len() - offset),
+            Err(error) if is_unreadable(&error) => 0,
+            Err(error) => return Err(error),
+        };
+        if read == 0 {
+            /* Only a read failing right at the address skips its page, a short
+            read may have stopped at the end of a region followed by another */
+            let failed = address + offset as u64;
+            let next_page = (failed / page_size + 1) * page_size;
+            offset = ((next_page - address) as usize).min(buffer.len());
+            continue;
+        }
+        match parts.last_mut() {
+            Some(part) if part.end == offset => part.end += read,
+            _ => parts.push(offset..offset + read),
+        }
+        offset += read;
+    }
+    Ok(parts)
+}
+
+/// Whether a failed read only means the memory at the address can't be read,
+/// like a guard page or a region unmapped meanwhile, rather than that the
+/// source can't be read at all, like a process that exited.
+pub fn is_unreadable(error: &io::Error) -> bool {
+    const EIO: i32 = 5;
+    const EFAULT: i32 = 14;
+    matches!(error.raw_os_error(), Some(EIO | EFAULT))
+        || error.kind() == io::ErrorKind::InvalidInput
+}
+
+fn unmapped(address: u64) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("address {:#x} is not mapped", address),
+    )
+}
+
+fn read_string(reader: &mut impl Read) -> io::Result<String> {
+    String::from_utf8(read_bytes(reader)?)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "string is not UTF-8"))
+}
+
+/// Writes the metadata of a region as varints, for snapshots and the remote
+/// protocol.
+pub(crate) fn write_region(writer: &mut impl Write, region: &MemoryRegion) -> io::Result<()> {
+    let flags = [
+        (region.readable, READABLE),
+        (region.writable, WRITABLE),
+        (region.executable, EXECUTABLE),
+        (region.shared, SHARED),
+    ]
+    .iter()
+    .filter(|(set, _)| *set)
+    .fold(0, |flags, (_, flag)| flags | flag);
+    write_u64(writer, region.start)?;
+    write_u64(writer, region.end - region.start)?;
+    write_u64(writer, flags)?;
+    write_u64(writer, region.offset)?;
+    write_bytes(
+        writer,
+        region.pathname.as_deref().unwrap_or_default().as_bytes(),
+    )
+}
+
+pub(crate) fn read_region(reader: &mut impl Read) -> io::Result<MemoryRegion> {
+    let start = read_u64(reader)?;
+    let end = start
+        .checked_add(read_u64(reader)?)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "region out of range"))?;
+    let flags = read_u64(reader)?;
+    let offset = read_u64(reader)?;
+    let pathname = read_string(reader)?;
+    Ok(MemoryRegion {
+        start,
+        end,
+        readable: flags & READABLE != 0,
+        writable: flags & WRITABLE != 0,
+        executable: flags & EXECUTABLE != 0,
+        shared: flags & SHARED != 0,
+        pathname: (!pathname.is_empty()).then_some(pathname),
+        offset,
+    })
+}
+
+/// Parses the content of `/proc/<pid>/maps`, lines look like
+/// `7f4b5c200000-7f4b5c221000 rw-p 00000000 00:00 0    [heap]`.
+pub fn parse_maps(maps: &str) -> Vec<MemoryRegion> {
+    maps.lines()
+        .filter_map(|line| {
+            /* Only the pathname is padded, it may contain spaces itself */
+            let mut fields = line.splitn(6, ' ');
+            let (start, end) = fields.next()?.split_once('-')?;
+            let perms = fields.next()?.as_bytes();
+            let offset = fields.next()?;
+            let pathname = fields.nth(2).unwrap_or_default().trim_start();
+            Some(MemoryRegion {
+                start: u64::from_str_radix(start, 16).ok()?,
+                end: u64::from_str_radix(end, 16).ok()?,
+                readable: perms.first() == Some(&b'r'),
+                writable: perms.get(1) == Some(&b'w'),
+                executable: perms.get(2) == Some(&b'x'),
+                shared: perms.get(3) == Some(&b's'),
+                pathname: (!pathname.is_empty()).then(|| pathname.to_string()),
+                offset: u64::from_str_radix(offset, 16).ok()?,
+            })
+        })
+        .collect()
+}
+
+/// The mapped regions of a live process.
+pub fn process_regions(pid: i32) -> io::Result<Vec<MemoryRegion>> {
+    Ok(parse_maps(&fs::read_to_string(format!(
+        "/proc/{}/maps",
+        pid
+    ))?))
+}
+
+/// Reads and writes a live process through `/proc/<pid>/mem`, which also
+/// allows writing to read only mappings.
+pub struct ProcMemSource {
+    pid: i32,
+    mem: File,
+}
+
+impl ProcMemSource {
+    pub fn open(pid: i32, writable: bool) -> io::Result<Self> {
+        let mem = OpenOptions::new()
+            .read(true)
+            .write(writable)
+            .open(format!("/proc/{}/mem", pid))?;
+        Ok(Self { pid, mem })
+    }
+}
+
+impl MemorySource for ProcMemSource {
+    fn regions(&self) -> io::Result<Vec<MemoryRegion>> {
+        process_regions(self.pid)
+    }
+
+    fn read(&self, address: u64, buffer: &mut [u8]) -> io::Result<usize> {
+        let mut read = 0;
+        while read < buffer.len() {
+            match self.mem.read_at(&mut buffer[read..], address + read as u64) {
+                Ok(0) => break,
+                Ok(length) => read += length,
+                Err(error) if read == 0 => return Err(error),
+                Err(_) => break,
+            }
+        }
+        Ok(read)
+    }
+
+    fn write(&mut self, address: u64, data: &[u8]) -> io::Result<usize> {
+        self.mem.write_at(data, address)
+    }
+}
+
+/// Reads and writes a live process with `process_vm_readv` and
+/// `process_vm_writev`, which needs no open file per process.
+pub struct ProcessVmSource {
+    pid: i32,
+}
+
+impl ProcessVmSource {
+    pub fn new(pid: i32) -> Self {
+        Self { pid }
+    }
+}
+
+impl MemorySource for ProcessVmSource {
+    fn regions(&self) -> io::Result<Vec<MemoryRegion>> {
+        process_regions(self.pid)
+    }
+
+    fn read(&self, address: u64, buffer: &mut [u8]) -> io::Result<usize> {
+        let local_iov = [iovec {
+            iov_base: buffer.as_mut_ptr() as *mut _,
+            iov_len: buffer.len(),
+        }];
+        let remote_iov = [iovec {
+            iov_base: address as *mut _,
+            iov_len: buffer.len(),
+        }];
+
+        let read_size =
+            unsafe { process_vm_readv(self.pid, &local_iov as _, 1, &remote_iov as _, 1, 0) };
+        if read_size < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(read_size as usize)
+    }
+
+    fn write(&mut self, address: u64, data: &[u8]) -> io::Result<usize> {
+        let local_iov = [iovec {
+            iov_base: data.as_ptr() as *mut _,
+            iov_len: data.len(),
+        }];
+        let remote_iov = [iovec {
+            iov_base: address as *mut _,
+            iov_len: data.len(),
+        }];
+
+        let write_size =
+            unsafe { process_vm_writev(self.pid, &local_iov as _, 1, &remote_iov as _, 1, 0) };
+        if write_size < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(write_size as usize)
+    }
+}
+
+/// Memory held in local buffers mapped at arbitrary addresses, for tests and
+/// for memory that was loaded from files.
+#[derive(Default)]
+pub struct BufferSource {
+    /// Start address and content of every region, sorted by address
+    buffers: Vec<(u64, Vec<u8>)>,
+}
+
+impl BufferSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps `bytes` at `start`, the region must not overlap existing ones.
+    pub fn map(&mut self, start: u64, bytes: Vec<u8>) {
+        let index = self.buffers.partition_point(|(other, _)| *other < start);
+        self.buffers.insert(index, (start, bytes));
+    }
+
+    /// Index of the buffer that maps `address`.
+    fn index_of(&self, address: u64) -> Option<usize> {
+        let index = self
+            .buffers
+            .partition_point(|(start, _)| *start <= address)
+            .checked_sub(1)?;
+        let (start, bytes) = &self.buffers[index];
+        (address - start < bytes.len() as u64).then_some(index)
+    }
+}
+
+impl MemorySource for BufferSource {
+    fn regions(&self) -> io::Result<Vec<MemoryRegion>> {
+        Ok(self
+            .buffers
+            .iter()
+            .map(|(start, bytes)| MemoryRegion {
+                start: *start,
+                end: start + bytes.len() as u64,
+                readable: true,
+                writable: true,
+                executable: false,
+                shared: false,
+                pathname: None,
+                offset: 0,
+            })
+            .collect())
+    }
+
+    fn read(&self, address: u64, buffer: &mut [u8]) -> io::Result<usize> {
+        let index = self.index_of(address).ok_or_else(|| unmapped(address))?;
+        let (start, bytes) = &self.buffers[index];
+        let available = &bytes[(address - start) as usize..];
+        let length = buffer.len().min(available.len());
+        buffer[..length].copy_from_slice(&available[..length]);
+        Ok(length)
+    }
+
+    fn write(&mut self, address: u64, data: &[u8]) -> io::Result<usize> {
+        let index = self.index_of(address).ok_or_else(|| unmapped(address))?;
+        let (start, bytes) = &mut self.buffers[index];
+        let available = &mut bytes[(address - *start) as usize..];
+        let length = data.len().min(available.len());
+        available[..length].copy_from_slice(&data[..length]);
+        Ok(length)
+    }
+}
+
+#[test]
+fn test_parse_maps() {
+    let regions = parse_maps(
+        "55d4c2a00000-55d4c2a21000 r-xp 00001000 08:01 1234                       /usr/bin/my game\n\
+         55d4c3e00000-55d4c3e21000 rw-p 00000000 00:00 0                          [heap]\n\
+         7ffd1e000000-7ffd1e021000 rw-p 00000000 00:00 0 \n",
+    );
+    assert_eq!(
+        regions,
+        [
+            MemoryRegion {
+                start: 0x55d4c2a00000,
+                end: 0x55d4c2a21000,
+                readable: true,
+                writable: false,
+                executable: true,
+                shared: false,
+                pathname: Some("/usr/bin/my game".to_string()),
+                offset: 0x1000,
+            },
+            MemoryRegion {
+                start: 0x55d4c3e00000,
+                end: 0x55d4c3e21000,
+                readable: true,
+                writable: true,
+                executable: false,
+                shared: false,
+                pathname: Some("[heap]".to_string()),
+                offset: 0,
+            },
+            MemoryRegion {
+                start: 0x7ffd1e000000,
+                end: 0x7ffd1e021000,
+                readable: true,
+                writable: true,
+                executable: false,
+                shared: false,
+                pathname: None,
+                offset: 0,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_memory_source() {
+    let mut source = BufferSource::new();
+    source.map(0x2000, vec![5, 6]);
+    source.map(0x1000, vec![1, 2, 3, 4]);
+    assert_eq!(source.regions().unwrap()[0].end, 0x1004);
+
+    let mut buffer = [0u8; 8];
+    assert_eq!(source.read(0x1001, &mut buffer).unwrap(), 3);
+    assert_eq!(buffer[..3], [2, 3, 4]);
+    assert!(source.read(0x1004, &mut buffer).is_err());
+    assert_eq!(source.write(0x2001, &[9, 9]).unwrap(), 1);
+    assert_eq!(source.read(0x2000, &mut buffer).unwrap(), 2);
+    assert_eq!(buffer[..2], [5, 9]);
+
+    let local = vec![0x5au8, 0xa5, 0x5a, 0xa5];
+    let address = local.as_ptr() as u64;
+    let source = ProcMemSource::open(std::process::id() as i32, false).unwrap();
+    assert!(
+        source
+            .regions()
+            .unwrap()
+            .iter()
+            .any(|region| region.contains(address) && region.writable)
+    );
+    let mut buffer = [0u8; 4];
+    assert_eq!(source.read(address, &mut buffer).unwrap(), 4);
+    assert_eq!(buffer.as_slice(), local.as_slice());
+}
+
+#[test]
+fn test_read_pages() {
+    let mut source = BufferSource::new();
+    source.map(0x1000, vec![1; 0x1000]);
+    source.map(0x3000, vec![2; 0x1800]);
+
+    let mut buffer = vec![0u8; 0x3000];
+    let parts = read_pages(&source, 0x1800, &mut buffer, 0x1000).unwrap();
+    assert_eq!(parts, [0..0x800, 0x1800..0x3000]);
+    assert!(buffer[..0x800].iter().all(|&byte| byte == 1));
+    assert!(buffer[0x1800..].iter().all(|&byte| byte == 2));
+
+    /* The rest of a partially mapped page is skipped */
+    let mut buffer = vec![0u8; 0x1000];
+    let parts = read_pages(&source, 0x4000, &mut buffer, 0x1000).unwrap();
+    assert_eq!(parts.len(), 1);
+    assert_eq!(parts[0], 0..0x800);
+    assert!(
+        read_pages(&source, 0x5000, &mut buffer, 0x1000)
+            .unwrap()
+            .is_empty()
+    );
+
+    let found = source.find_bytes(0x1000, 0x4800, &[1, 1], 1).unwrap();
+    assert_eq!(found.len(), 0xfff);
+
+    /* Reads stopping at the end of a region continue in the adjacent one */
+    source.map(0x2000, vec![3; 0x1000]);
+    let mut buffer = vec![0u8; 0x2000];
+    let parts = read_pages(&source, 0x1000, &mut buffer, 0x1000).unwrap();
+    assert_eq!(parts.len(), 1);
+    assert_eq!(parts[0], 0..0x2000);
+    assert_eq!((buffer[0xfff], buffer[0x1000]), (1, 3));
+
+    /* Errors other than unreadable memory end the read */
+    assert!(read_pages(&ExitedSource, 0x1000, &mut buffer, 0x1000).is_err());
+    assert!(ExitedSource.find_bytes(0x1000, 0x2000, &[1], 1).is_err());
+}
+
+/// A process that exited, every read fails with `ESRCH`.
+#[cfg(test)]
+pub(crate) struct ExitedSource;
+
+#[cfg(test)]
+impl MemorySource for ExitedSource {
+    fn regions(&self) -> io::Result<Vec<MemoryRegion>> {
+        Ok(vec![])
+    }
+
+    fn read(&self, _address: u64, _buffer: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::from_raw_os_error(3))
+    }
+}
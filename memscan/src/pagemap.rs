@@ -0,0 +1,181 @@
+use std::{
+    fs::{self, File},
+    io,
+    ops::Range,
+    os::unix::fs::FileExt,
+    sync::OnceLock,
+};
+
+/// The page is in memory.
+pub const PRESENT: u64 = 1 << 63;
+/// The page is in swap.
+pub const SWAPPED: u64 = 1 << 62;
+/// The page was written since the soft-dirty bits were cleared.
+pub const SOFT_DIRTY: u64 = 1 << 55;
+
+/// Entries read from the pagemap at once.
+const ENTRIES_PER_READ: usize = 64 * 1024;
+const AT_PAGESZ: u64 = 6;
+
+/// Size of a memory page, from the auxiliary vector.
+pub fn page_size() -> u64 {
+    static PAGE_SIZE: OnceLock<u64> = OnceLock::new();
+    *PAGE_SIZE.get_or_init(|| {
+        fs::read("/proc/self/auxv")
+            .ok()
+            .and_then(|auxv| {
+                auxv.chunks_exact(16).find_map(|entry| {
+                    let key = u64::from_ne_bytes(entry[..8].try_into().unwrap());
+                    (key == AT_PAGESZ).then(|| u64::from_ne_bytes(entry[8..].try_into().unwrap()))
+                })
+            })
+            .unwrap_or(4096)
+    })
+}
+
+/// Clears the soft-dirty bits of every page of `pid`, so pages written from
+/// now on can be told apart.
+pub fn clear_soft_dirty(pid: i32) -> io::Result<()> {
+    fs::write(format!("/proc/{}/clear_refs", pid), "4")
+}
+
+/// Whether the kernel tracks soft-dirty bits, tested on this process. Without
+/// `CONFIG_MEM_SOFT_DIRTY` clearing them succeeds but they are never set.
+pub fn soft_dirty_supported() -> bool {
+    static SUPPORTED: OnceLock<bool> = OnceLock::new();
+    *SUPPORTED.get_or_init(|| {
+        let pid = std::process::id() as i32;
+        let mut page = vec![0u8; page_size() as usize];
+        let address = page.as_mut_ptr();
+        let Ok(pagemap) = Pagemap::open(pid) else {
+            return false;
+        };
+        if clear_soft_dirty(pid).is_err() {
+            return false;
+        }
+        unsafe { address.write_volatile(1) };
+        pagemap
+            .soft_dirty(address as u64, address as u64 + 1)
+            .is_ok_and(|ranges| !ranges.is_empty())
+    })
+}
+
+/// The `/proc/<pid>/pagemap` of a process, which has a 64 bit entry with the
+/// state of every virtual page.
+pub struct Pagemap {
+    file: File,
+    page_size: u64,
+}
+
+impl Pagemap {
+    pub fn open(pid: i32) -> io::Result<Self> {
+        Ok(Self {
+            file: File::open(format!("/proc/{}/pagemap", pid))?,
+            page_size: page_size(),
+        })
+    }
+
+    /// The parts of `start..end` whose pages have an entry matching
+    /// `filter`, with adjacent pages merged.
+    pub fn ranges(
+        &self,
+        start: u64,
+        end: u64,
+        filter: impl Fn(u64) -> bool,
+    ) -> io::Result<Vec<Range<u64>>> {
+        let mut ranges: Vec<Range<u64>> = vec![];
+        let mut buffer = vec![0u8; ENTRIES_PER_READ * 8];
+        let end_page = end.div_ceil(self.page_size);
+        let mut page = start / self.page_size;
+        while page < end_page {
+            let count = ((end_page - page) as usize).min(ENTRIES_PER_READ);
+            let entries = &mut buffer[..count * 8];
+            self.file.read_exact_at(entries, page * 8)?;
+            for (index, entry) in entries.chunks_exact(8).enumerate() {
+                if !filter(u64::from_ne_bytes(entry.try_into().unwrap())) {
+                    continue;
+                }
+                let page_start = (page + index as u64) * self.page_size;
+                match ranges.last_mut() {
+                    Some(last) if last.end == page_start => last.end += self.page_size,
+                    _ => ranges.push(page_start..page_start + self.page_size),
+                }
+            }
+            page += count as u64;
+        }
+
+        /* The first and last page may stick out of the requested range */
+        if let Some(first) = ranges.first_mut() {
+            first.start = first.start.max(start);
+        }
+        if let Some(last) = ranges.last_mut() {
+            last.end = last.end.min(end);
+        }
+        Ok(ranges)
+    }
+
+    /// The parts of `start..end` that were ever written to. Pages that are
+    /// neither present nor swapped read as zeros, or as the mapped file.
+    pub fn populated(
+        &self,
+        start: u64,
+        end: u64,
+        include_swapped: bool,
+    ) -> io::Result<Vec<Range<u64>>> {
+        let mask = if include_swapped {
+            PRESENT | SWAPPED
+        } else {
+            PRESENT
+        };
+        self.ranges(start, end, |entry| entry & mask != 0)
+    }
+
+    /// The parts of `start..end` written since `clear_soft_dirty`.
+    pub fn soft_dirty(&self, start: u64, end: u64) -> io::Result<Vec<Range<u64>>> {
+        self.ranges(start, end, |entry| entry & SOFT_DIRTY != 0)
+    }
+}
+
+#[test]
+fn test_pagemap() {
+    let page_size = page_size();
+    assert!(page_size.is_power_of_two());
+
+    /* Large enough to get fresh, untouched pages from mmap */
+    let length = 64 * 1024 * 1024;
+    let mut buffer = vec![0u8; length];
+    let start = buffer.as_ptr() as u64;
+    for page in [3, 10, 11] {
+        buffer[page * page_size as usize + 5] = 1;
+    }
+
+    let pagemap = Pagemap::open(std::process::id() as i32).unwrap();
+    let populated = pagemap
+        .populated(start, start + length as u64, true)
+        .unwrap();
+    for page in [3, 10, 11] {
+        let address = start + page * page_size;
+        assert!(populated.iter().any(|range| range.contains(&address)));
+    }
+    let total: u64 = populated.iter().map(|range| range.end - range.start).sum();
+    assert!(total < length as u64);
+    assert!(
+        populated
+            .iter()
+            .all(|range| start <= range.start && range.end <= start + length as u64)
+    );
+    assert!(populated.windows(2).all(|pair| pair[0].end < pair[1].start));
+
+    if soft_dirty_supported() {
+        clear_soft_dirty(std::process::id() as i32).unwrap();
+        buffer[10 * page_size as usize] = 2;
+        let dirty = pagemap.soft_dirty(start, start + length as u64).unwrap();
+        let page = start + 10 * page_size;
+        assert!(dirty.iter().any(|range| range.contains(&page)));
+        assert!(
+            !dirty
+                .iter()
+                .any(|range| range.contains(&(start + 3 * page_size)))
+        );
+    }
+}
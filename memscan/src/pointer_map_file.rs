@@ -0,0 +1,194 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::{
+    pointer_scan::{MappedRegion, PointerChain, PointerMap},
+    varint::{read_bytes, read_i64, read_u64, write_bytes, write_i64, write_u64},
+};
+
+const MAGIC: &[u8; 4] = b"MSPM";
+const VERSION: u32 = 2;
+
+/// A pointer map together with the address that was scanned for, so chains
+/// found in one run can be checked against the maps of other runs.
+///
+/// Layout after the magic and the little endian version, all integers varints:
+/// a byte that is 1 if a target follows and the target, the region count followed by
+/// start, length and module name (empty for none) of each region, and the
+/// pointer count followed by the address delta and the signed value delta of
+/// each pointer, in address order.
+pub struct SavedPointerMap {
+    pub target: Option<u64>,
+    pub map: PointerMap,
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl SavedPointerMap {
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&[self.target.is_some() as u8])?;
+        if let Some(target) = self.target {
+            write_u64(writer, target)?;
+        }
+
+        let regions = self.map.regions();
+        write_u64(writer, regions.len() as u64)?;
+        for region in regions {
+            write_u64(writer, region.start)?;
+            write_u64(writer, region.end - region.start)?;
+            write_bytes(writer, region.module.as_deref().unwrap_or("").as_bytes())?;
+        }
+
+        let pointers = self.map.pointers_by_address();
+        write_u64(writer, pointers.len() as u64)?;
+        let (mut last_address, mut last_value) = (0u64, 0u64);
+        for &(address, value) in pointers {
+            write_u64(writer, address - last_address)?;
+            write_i64(writer, value.wrapping_sub(last_value) as i64)?;
+            (last_address, last_value) = (address, value);
+        }
+        Ok(())
+    }
+
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid_data("not a pointer map file"));
+        }
+        let version = u32::from_le_bytes(header[4..].try_into().unwrap());
+        if version != VERSION {
+            return Err(invalid_data(&format!(
+                "unsupported pointer map version {}",
+                version
+            )));
+        }
+        let mut has_target = [0u8];
+        reader.read_exact(&mut has_target)?;
+        let target = match has_target[0] {
+            0 => None,
+            1 => Some(read_u64(reader)?),
+            _ => return Err(invalid_data("invalid target flag")),
+        };
+
+        let region_count = read_u64(reader)?;
+        let mut regions = vec![];
+        for _ in 0..region_count {
+            let start = read_u64(reader)?;
+            let end = start
+                .checked_add(read_u64(reader)?)
+                .ok_or_else(|| invalid_data("region out of range"))?;
+            let module = String::from_utf8(read_bytes(reader)?)
+                .map_err(|_| invalid_data("module name is not UTF-8"))?;
+            regions.push(MappedRegion {
+                start,
+                end,
+                module: (!module.is_empty()).then_some(module),
+            });
+        }
+
+        let mut map = PointerMap::new(regions);
+        let pointer_count = read_u64(reader)?;
+        let (mut address, mut value) = (0u64, 0u64);
+        for _ in 0..pointer_count {
+            address = address
+                .checked_add(read_u64(reader)?)
+                .ok_or_else(|| invalid_data("pointer out of range"))?;
+            value = value.wrapping_add(read_i64(reader)? as u64);
+            map.add_pointer(address, value);
+        }
+        map.finish();
+
+        Ok(Self { target, map })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read_from(&mut BufReader::new(File::open(path)?))
+    }
+
+    /// Whether `chain` leads to the target in this map as well.
+    pub fn confirms(&self, chain: &PointerChain) -> bool {
+        self.target
+            .is_some_and(|target| self.map.resolve(chain) == Some(target))
+    }
+}
+
+/// Keeps the chains that resolve to the saved target in every map.
+pub fn intersect_chains(chains: &mut Vec<PointerChain>, maps: &[SavedPointerMap]) {
+    chains.retain(|chain| maps.iter().all(|map| map.confirms(chain)));
+}
+
+#[test]
+fn test_pointer_map_file() {
+    let regions = vec![
+        MappedRegion {
+            start: 0x1000,
+            end: 0x2000,
+            module: Some("libgame.so".to_string()),
+        },
+        MappedRegion {
+            start: 0x10000,
+            end: 0x11000,
+            module: None,
+        },
+    ];
+    let mut map = PointerMap::new(regions.clone());
+    map.add_pointer(0x1100, 0x10200);
+    map.add_pointer(0x10218, 0x10800);
+    map.add_pointer(0x10000, 0x1000);
+    map.finish();
+    let saved = SavedPointerMap {
+        target: Some(0x10840),
+        map,
+    };
+    let mut highest = vec![];
+    SavedPointerMap {
+        target: Some(u64::MAX),
+        map: PointerMap::new(vec![]),
+    }
+    .write_to(&mut highest)
+    .unwrap();
+    let loaded = SavedPointerMap::read_from(&mut highest.as_slice()).unwrap();
+    assert_eq!(loaded.target, Some(u64::MAX));
+
+    let mut buffer = vec![];
+    saved.write_to(&mut buffer).unwrap();
+    let loaded = SavedPointerMap::read_from(&mut buffer.as_slice()).unwrap();
+    assert_eq!(loaded.target, Some(0x10840));
+    assert_eq!(loaded.map.regions(), regions.as_slice());
+    assert_eq!(
+        loaded.map.pointers_by_address(),
+        saved.map.pointers_by_address()
+    );
+
+    let survivor = PointerChain {
+        module: "libgame.so".to_string(),
+        base_offset: 0x100,
+        offsets: vec![0x18, 0x40],
+    };
+    let broken = PointerChain {
+        module: "libgame.so".to_string(),
+        base_offset: 0x108,
+        offsets: vec![0x40],
+    };
+    let mut chains = vec![survivor.clone(), broken];
+    intersect_chains(&mut chains, &[loaded]);
+    assert_eq!(chains, vec![survivor]);
+
+    buffer[4] = 1;
+    assert!(SavedPointerMap::read_from(&mut buffer.as_slice()).is_err());
+    assert!(SavedPointerMap::read_from(&mut &b"MSPX"[..]).is_err());
+}
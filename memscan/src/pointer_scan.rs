@@ -0,0 +1,365 @@
+use std::{collections::HashMap, fmt::Display, rc::Rc};
+
+use crate::search::InclusiveRangeSearch;
+
+/// A mapped region of the target. `module` is set for module backed regions,
+/// which are the roots of pointer chains.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MappedRegion {
+    pub start: u64,
+    pub end: u64,
+    pub module: Option<String>,
+}
+
+/// Every pointer sized value in the scanned memory that points into a mapped
+/// region, sorted by the value it points to.
+#[derive(Default)]
+pub struct PointerMap {
+    regions: Vec<MappedRegion>,
+    /// `(value, address)` pairs
+    pointers: Vec<(u64, u64)>,
+    /// `(address, value)` pairs for following chains
+    by_address: Vec<(u64, u64)>,
+}
+
+impl PointerMap {
+    pub fn new(mut regions: Vec<MappedRegion>) -> Self {
+        regions.sort_by_key(|region| region.start);
+        Self {
+            regions,
+            pointers: vec![],
+            by_address: vec![],
+        }
+    }
+
+    pub fn regions(&self) -> &[MappedRegion] {
+        &self.regions
+    }
+
+    pub fn len(&self) -> usize {
+        self.pointers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pointers.is_empty()
+    }
+
+    pub fn region_of(&self, address: u64) -> Option<&MappedRegion> {
+        let index = self.regions.partition_point(|region| region.end <= address);
+        self.regions
+            .get(index)
+            .filter(|region| region.start <= address)
+    }
+
+    /// Lowest address of the module, offsets in chains are relative to it.
+    pub fn module_base(&self, module: &str) -> Option<u64> {
+        self.regions
+            .iter()
+            .find(|region| region.module.as_deref() == Some(module))
+            .map(|region| region.start)
+    }
+
+    /// Collects the pointers in `memory`, which was read from `address`.
+    /// Call `finish` once all memory was added.
+    pub fn add_memory(&mut self, address: u64, memory: &[u8]) {
+        let (Some(first), Some(last)) = (self.regions.first(), self.regions.last()) else {
+            return;
+        };
+
+        /* Rough filter on the whole address space, holes are checked afterwards */
+        let search = InclusiveRangeSearch::new(first.start, last.end - 1, memory);
+        for offset in search {
+            let value = u64::from_ne_bytes(memory[offset..offset + 8].try_into().unwrap());
+            if self.region_of(value).is_some() {
+                self.pointers.push((value, address + offset as u64));
+            }
+        }
+    }
+
+    pub fn add_pointer(&mut self, address: u64, value: u64) {
+        self.pointers.push((value, address));
+    }
+
+    pub fn finish(&mut self) {
+        self.pointers.sort_unstable();
+        self.pointers.dedup();
+        self.by_address = self
+            .pointers
+            .iter()
+            .map(|&(value, address)| (address, value))
+            .collect();
+        self.by_address.sort_unstable();
+    }
+
+    /// All pointers as `(address, value)`, sorted by address.
+    pub fn pointers_by_address(&self) -> &[(u64, u64)] {
+        &self.by_address
+    }
+
+    pub fn read_pointer(&self, address: u64) -> Option<u64> {
+        self.by_address
+            .binary_search_by_key(&address, |&(address, _)| address)
+            .ok()
+            .map(|index| self.by_address[index].1)
+    }
+
+    /// Follows the chain through this map and returns the address it ends at.
+    pub fn resolve(&self, chain: &PointerChain) -> Option<u64> {
+        let mut address = self.module_base(&chain.module)? + chain.base_offset;
+        for offset in &chain.offsets {
+            address = self.read_pointer(address)?.checked_add(*offset)?;
+        }
+        Some(address)
+    }
+
+    /// Pointers whose value lies within `lower..=upper`, as `(value, address)`.
+    pub fn pointers_into(&self, lower: u64, upper: u64) -> &[(u64, u64)] {
+        let start = self.pointers.partition_point(|&(value, _)| value < lower);
+        let end = self.pointers.partition_point(|&(value, _)| value <= upper);
+        &self.pointers[start..end.max(start)]
+    }
+}
+
+/// A path from a module backed address to the target. The pointer stored at
+/// `module + base_offset` is dereferenced and `offsets` are applied in turn,
+/// dereferencing in between.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PointerChain {
+    pub module: String,
+    pub base_offset: u64,
+    pub offsets: Vec<u64>,
+}
+
+impl Display for PointerChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}+{:#X}", self.module, self.base_offset)?;
+        for offset in &self.offsets {
+            write!(f, " -> +{:#X}", offset)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct PointerScan<'a> {
+    map: &'a PointerMap,
+    max_offset: u64,
+    max_depth: usize,
+    max_results: usize,
+    max_nodes: usize,
+}
+
+/// State of one scan: the chains found to every address for the number of
+/// offsets they may still take, and the number of expanded addresses.
+struct Walk {
+    reaching: HashMap<(u64, usize), Rc<Vec<PointerChain>>>,
+    nodes: usize,
+}
+
+impl<'a> PointerScan<'a> {
+    pub fn new(map: &'a PointerMap, max_offset: u64, max_depth: usize) -> Self {
+        Self {
+            map,
+            max_offset,
+            max_depth,
+            max_results: 10_000,
+            max_nodes: 1_000_000,
+        }
+    }
+
+    /// Stop after expanding this many addresses, which bounds the work when
+    /// few chains reach a module.
+    pub fn max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = max_nodes;
+        self
+    }
+
+    /// Stop after this many chains, the number of chains grows exponentially
+    /// with the depth.
+    pub fn max_results(mut self, max_results: usize) -> Self {
+        self.max_results = max_results;
+        self
+    }
+
+    /// Walks the pointer map backwards from `target` and returns every chain
+    /// of up to `max_depth` pointers that is rooted in a module.
+    pub fn scan(&self, target: u64) -> Vec<PointerChain> {
+        let mut walk = Walk {
+            reaching: HashMap::new(),
+            nodes: 0,
+        };
+        let chains = self.walk(target, self.max_depth, &mut walk);
+        Rc::try_unwrap(chains).unwrap_or_else(|chains| (*chains).clone())
+    }
+
+    /// Chains of up to `depth` offsets that end at `address`. Paths meeting
+    /// at the same address share its chains, the offsets walked so far are
+    /// appended by the caller.
+    fn walk(&self, address: u64, depth: usize, walk: &mut Walk) -> Rc<Vec<PointerChain>> {
+        if depth == 0 || walk.nodes >= self.max_nodes {
+            return Rc::default();
+        }
+        if let Some(chains) = walk.reaching.get(&(address, depth)) {
+            return chains.clone();
+        }
+        walk.nodes += 1;
+
+        let mut chains = vec![];
+        let lower = address.saturating_sub(self.max_offset);
+        for &(value, pointer) in self.map.pointers_into(lower, address) {
+            if chains.len() >= self.max_results {
+                break;
+            }
+            let offset = address - value;
+
+            if let Some(module) = self
+                .map
+                .region_of(pointer)
+                .and_then(|region| region.module.as_deref())
+            {
+                let base = self.map.module_base(module).unwrap_or(pointer);
+                chains.push(PointerChain {
+                    module: module.to_string(),
+                    base_offset: pointer - base,
+                    offsets: vec![offset],
+                });
+            }
+            for chain in self.walk(pointer, depth - 1, walk).iter() {
+                if chains.len() >= self.max_results {
+                    break;
+                }
+                let mut chain = chain.clone();
+                chain.offsets.push(offset);
+                chains.push(chain);
+            }
+        }
+
+        let chains = Rc::new(chains);
+        walk.reaching.insert((address, depth), chains.clone());
+        chains
+    }
+}
+
+#[cfg(test)]
+fn test_map() -> PointerMap {
+    /* Module at 0x1000, heap at 0x10000 */
+    let regions = vec![
+        MappedRegion {
+            start: 0x1000,
+            end: 0x2000,
+            module: Some("libgame.so".to_string()),
+        },
+        MappedRegion {
+            start: 0x10000,
+            end: 0x11000,
+            module: None,
+        },
+    ];
+    let mut map = PointerMap::new(regions);
+
+    /* libgame.so+0x100 -> 0x10200, 0x10200 + 0x18 -> 0x10800, target 0x10800 + 0x40 */
+    let mut module = vec![0u64; 0x1000 / 8];
+    module[0x100 / 8] = 0x10200;
+    module[0x108 / 8] = 0x5000;
+    let mut heap = vec![0u64; 0x1000 / 8];
+    heap[(0x200 + 0x18) / 8] = 0x10800;
+
+    let bytes = |memory: &[u64]| -> Vec<u8> {
+        memory
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect()
+    };
+    map.add_memory(0x1000, &bytes(&module));
+    map.add_memory(0x10000, &bytes(&heap));
+    map.finish();
+    map
+}
+
+#[test]
+fn test_pointer_map() {
+    let map = test_map();
+    assert_eq!(map.len(), 2, "0x5000 is not mapped");
+    assert_eq!(
+        map.pointers_into(0x10000, 0x10800),
+        &[(0x10200, 0x1100), (0x10800, 0x10218)]
+    );
+    assert_eq!(map.pointers_into(0x10201, 0x107FF), &[]);
+    assert_eq!(map.module_base("libgame.so"), Some(0x1000));
+    assert_eq!(map.read_pointer(0x10218), Some(0x10800));
+    assert_eq!(map.read_pointer(0x10210), None);
+}
+
+#[test]
+fn test_pointer_scan() {
+    let map = test_map();
+    let chains = PointerScan::new(&map, 0x100, 3).scan(0x10840);
+    assert_eq!(
+        chains,
+        vec![PointerChain {
+            module: "libgame.so".to_string(),
+            base_offset: 0x100,
+            offsets: vec![0x18, 0x40],
+        }]
+    );
+    assert_eq!(chains[0].to_string(), "libgame.so+0x100 -> +0x18 -> +0x40");
+    assert_eq!(map.resolve(&chains[0]), Some(0x10840));
+
+    assert!(PointerScan::new(&map, 0x100, 1).scan(0x10840).is_empty());
+    assert!(PointerScan::new(&map, 0x10, 3).scan(0x10840).is_empty());
+}
+
+#[test]
+fn test_pointer_scan_budget() {
+    /* Every word of the heap points into the heap, no chain reaches a module */
+    let regions = vec![MappedRegion {
+        start: 0x10000,
+        end: 0x11000,
+        module: None,
+    }];
+    let mut map = PointerMap::new(regions);
+    let heap: Vec<u8> = (0..0x1000u64 / 8)
+        .flat_map(|index| (0x10000 + index * 8).to_ne_bytes())
+        .collect();
+    map.add_memory(0x10000, &heap);
+    map.finish();
+
+    assert!(PointerScan::new(&map, 0x1000, 8).scan(0x10800).is_empty());
+
+    let map = test_map();
+    assert!(
+        PointerScan::new(&map, 0x100, 3)
+            .max_nodes(1)
+            .scan(0x10840)
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_pointer_scan_converging() {
+    let regions = vec![
+        MappedRegion {
+            start: 0x1000,
+            end: 0x2000,
+            module: Some("libgame.so".to_string()),
+        },
+        MappedRegion {
+            start: 0x10000,
+            end: 0x11000,
+            module: None,
+        },
+    ];
+    let mut map = PointerMap::new(regions);
+    /* Both pointers to the target are reached through the one at 0x10400 */
+    map.add_pointer(0x1100, 0x10400);
+    map.add_pointer(0x10400, 0x10600);
+    map.add_pointer(0x10610, 0x10800);
+    map.add_pointer(0x10620, 0x10820);
+    map.finish();
+
+    let chains = PointerScan::new(&map, 0x100, 3).scan(0x10840);
+    let offsets: Vec<_> = chains.iter().map(|chain| chain.offsets.clone()).collect();
+    assert_eq!(offsets, [[0, 0x10, 0x40], [0, 0x20, 0x20]]);
+    for chain in &chains {
+        assert_eq!(map.resolve(chain), Some(0x10840));
+    }
+}
@@ -0,0 +1,613 @@
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    fs,
+    io::{self, BufReader, BufWriter, Read, Write},
+    net::TcpStream,
+    os::unix::net::UnixStream,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    address_set::AddressSet,
+    memory_source::{MemoryRegion, MemorySource, read_region, write_region},
+    varint::{read_u64, write_bytes, write_u64},
+};
+
+const MAGIC: &[u8; 4] = b"MSRP";
+/// Both sides send their version after the magic and hang up on a mismatch.
+pub const PROTOCOL_VERSION: u32 = 3;
+
+/// Longest token the agent accepts.
+const MAX_TOKEN: u64 = 256;
+
+/// Port the agent listens on by default.
+pub const DEFAULT_PORT: u16 = 7331;
+
+/// Largest message accepted, reads and writes are split into parts of
+/// this size.
+const MAX_MESSAGE: u64 = 64 * 1024 * 1024;
+
+const LIST_PROCESSES: u64 = 1;
+const REGIONS: u64 = 2;
+const READ: u64 = 3;
+const WRITE: u64 = 4;
+const FIND_BYTES: u64 = 5;
+
+const OK: u64 = 0;
+const ERROR: u64 = 1;
+
+/// Kinds of errors that keep their meaning on the client, like unmapped
+/// addresses, which scans skip. Errors with an OS code carry their kind in it.
+const ERROR_KINDS: [io::ErrorKind; 7] = [
+    io::ErrorKind::Other,
+    io::ErrorKind::InvalidInput,
+    io::ErrorKind::InvalidData,
+    io::ErrorKind::NotFound,
+    io::ErrorKind::PermissionDenied,
+    io::ErrorKind::Unsupported,
+    io::ErrorKind::UnexpectedEof,
+];
+
+/// A process on the machine of the agent.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProcessInfo {
+    pub pid: i32,
+    pub uid: u32,
+    pub cmd: String,
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_message(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let length = read_u64(reader)?;
+    if length > MAX_MESSAGE {
+        return Err(invalid_data("message is too long"));
+    }
+    let mut bytes = vec![0u8; length as usize];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_string(reader: &mut impl Read) -> io::Result<String> {
+    String::from_utf8(read_message(reader)?).map_err(|_| invalid_data("string is not UTF-8"))
+}
+
+fn read_pid(reader: &mut impl Read) -> io::Result<i32> {
+    i32::try_from(read_u64(reader)?).map_err(|_| invalid_data("pid out of range"))
+}
+
+/// Both sides send the magic and their version first.
+fn write_header(writer: &mut impl Write) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&PROTOCOL_VERSION.to_le_bytes())?;
+    writer.flush()
+}
+
+fn check_header(reader: &mut impl Read) -> io::Result<()> {
+    let mut header = [0u8; 8];
+    reader.read_exact(&mut header)?;
+    if &header[..4] != MAGIC {
+        return Err(invalid_data("not a memscan agent"));
+    }
+    let version = u32::from_le_bytes(header[4..].try_into().unwrap());
+    if version != PROTOCOL_VERSION {
+        return Err(invalid_data(&format!(
+            "unsupported protocol version {}",
+            version
+        )));
+    }
+    Ok(())
+}
+
+/// Compares the tokens in constant time, so their content can't be guessed
+/// from how long the agent takes to reject one.
+fn same_token(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// The client sends the token after the header, the agent answers with a
+/// response without a body or an error and hangs up.
+fn check_token(reader: &mut impl Read, writer: &mut impl Write, token: &str) -> io::Result<()> {
+    let length = read_u64(reader)?;
+    if length > MAX_TOKEN {
+        return Err(invalid_data("token is too long"));
+    }
+    let mut sent = vec![0u8; length as usize];
+    reader.read_exact(&mut sent)?;
+    if same_token(&sent, token.as_bytes()) {
+        write_u64(writer, OK)?;
+        write_bytes(writer, &[])?;
+        return writer.flush();
+    }
+
+    let error = io::Error::new(io::ErrorKind::PermissionDenied, "invalid agent token");
+    write_error(writer, &error)?;
+    writer.flush()?;
+    Err(error)
+}
+
+/// Error responses hold the OS code, or 0, the index of the kind in
+/// `ERROR_KINDS` and the message.
+fn write_error(writer: &mut impl Write, error: &io::Error) -> io::Result<()> {
+    let kind = ERROR_KINDS
+        .iter()
+        .position(|&kind| kind == error.kind())
+        .unwrap_or(0);
+    let mut message = vec![];
+    write_u64(&mut message, error.raw_os_error().unwrap_or(0) as u64)?;
+    write_u64(&mut message, kind as u64)?;
+    write_bytes(&mut message, error.to_string().as_bytes())?;
+    write_u64(writer, ERROR)?;
+    write_bytes(writer, &message)
+}
+
+/// Reads the status and body of a response, errors of the agent are
+/// returned as `Err`.
+fn read_response(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let status = read_u64(reader)?;
+    let body = read_message(reader)?;
+    match status {
+        OK => Ok(body),
+        ERROR => {
+            let mut body = body.as_slice();
+            let code = read_u64(&mut body)? as i32;
+            let kind = ERROR_KINDS
+                .get(read_u64(&mut body)? as usize)
+                .copied()
+                .unwrap_or(io::ErrorKind::Other);
+            let message = read_string(&mut body)?;
+            Err(match code {
+                0 => io::Error::new(kind, message),
+                code => io::Error::from_raw_os_error(code),
+            })
+        }
+        _ => Err(invalid_data("unknown response status")),
+    }
+}
+
+/// The processes of this machine from `/proc`.
+pub fn list_processes() -> io::Result<Vec<ProcessInfo>> {
+    let mut processes = vec![];
+    for entry in fs::read_dir("/proc")? {
+        let Ok(pid) = entry?.file_name().to_string_lossy().parse::<i32>() else {
+            continue;
+        };
+        /* The process may have exited in the meantime */
+        let Ok(cmd) = fs::read_to_string(format!("/proc/{}/comm", pid)) else {
+            continue;
+        };
+        let uid = fs::read_to_string(format!("/proc/{}/status", pid))
+            .ok()
+            .and_then(|status| {
+                let line = status.lines().find_map(|line| line.strip_prefix("Uid:"))?;
+                line.split_whitespace().next()?.parse().ok()
+            })
+            .unwrap_or_default();
+        processes.push(ProcessInfo {
+            pid,
+            uid,
+            cmd: cmd.trim_end().to_string(),
+        });
+    }
+    processes.sort_by_key(|process| process.pid);
+    Ok(processes)
+}
+
+enum Request {
+    ListProcesses,
+    Regions {
+        pid: i32,
+    },
+    Read {
+        pid: i32,
+        address: u64,
+        length: u64,
+    },
+    Write {
+        pid: i32,
+        address: u64,
+        data: Vec<u8>,
+    },
+    FindBytes {
+        pid: i32,
+        start: u64,
+        end: u64,
+        needle: Vec<u8>,
+        alignment: u64,
+    },
+}
+
+/// The next request of the client, `None` once it hung up.
+fn read_request(reader: &mut impl Read) -> io::Result<Option<Request>> {
+    let opcode = match read_u64(reader) {
+        Ok(opcode) => opcode,
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error),
+    };
+    let request = match opcode {
+        LIST_PROCESSES => Request::ListProcesses,
+        REGIONS => Request::Regions {
+            pid: read_pid(reader)?,
+        },
+        READ => Request::Read {
+            pid: read_pid(reader)?,
+            address: read_u64(reader)?,
+            length: read_u64(reader)?.min(MAX_MESSAGE),
+        },
+        WRITE => Request::Write {
+            pid: read_pid(reader)?,
+            address: read_u64(reader)?,
+            data: read_message(reader)?,
+        },
+        FIND_BYTES => Request::FindBytes {
+            pid: read_pid(reader)?,
+            start: read_u64(reader)?,
+            end: read_u64(reader)?,
+            needle: read_message(reader)?,
+            alignment: read_u64(reader)?,
+        },
+        _ => return Err(invalid_data(&format!("unknown request {}", opcode))),
+    };
+    Ok(Some(request))
+}
+
+/// The source of `pid`, opened on first use.
+fn source_of<'a>(
+    sources: &'a mut HashMap<i32, Box<dyn MemorySource>>,
+    open: &mut impl FnMut(i32) -> io::Result<Box<dyn MemorySource>>,
+    pid: i32,
+) -> io::Result<&'a mut Box<dyn MemorySource>> {
+    match sources.entry(pid) {
+        Entry::Occupied(entry) => Ok(entry.into_mut()),
+        Entry::Vacant(entry) => Ok(entry.insert(open(pid)?)),
+    }
+}
+
+/// Runs `request` and writes its result into `response`.
+fn execute(
+    request: Request,
+    sources: &mut HashMap<i32, Box<dyn MemorySource>>,
+    open: &mut impl FnMut(i32) -> io::Result<Box<dyn MemorySource>>,
+    response: &mut Vec<u8>,
+) -> io::Result<()> {
+    match request {
+        Request::ListProcesses => {
+            let processes = list_processes()?;
+            write_u64(response, processes.len() as u64)?;
+            for process in processes {
+                write_u64(response, process.pid as u64)?;
+                write_u64(response, process.uid as u64)?;
+                write_bytes(response, process.cmd.as_bytes())?;
+            }
+        }
+        Request::Regions { pid } => {
+            let regions = source_of(sources, open, pid)?.regions()?;
+            write_u64(response, regions.len() as u64)?;
+            for region in &regions {
+                write_region(response, region)?;
+            }
+        }
+        Request::Read {
+            pid,
+            address,
+            length,
+        } => {
+            let mut buffer = vec![0u8; length as usize];
+            let read = source_of(sources, open, pid)?.read(address, &mut buffer)?;
+            response.extend_from_slice(&buffer[..read]);
+        }
+        Request::Write { pid, address, data } => {
+            let written = source_of(sources, open, pid)?.write(address, &data)?;
+            write_u64(response, written as u64)?;
+        }
+        Request::FindBytes {
+            pid,
+            start,
+            end,
+            needle,
+            alignment,
+        } => {
+            let source = source_of(sources, open, pid)?;
+            let found = source.find_bytes(start, end, &needle, alignment as usize)?;
+            write_u64(response, found.len() as u64)?;
+            let mut last = 0;
+            for address in &found {
+                write_u64(response, address - last)?;
+                last = address;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Answers the requests of one client until it hangs up, `open` gives the
+/// memory of a pid. Clients that don't send `token` are turned away. Failed
+/// requests are reported to the client, only broken connections and wrong
+/// tokens end with an error.
+pub fn serve(
+    reader: impl Read,
+    writer: impl Write,
+    token: &str,
+    mut open: impl FnMut(i32) -> io::Result<Box<dyn MemorySource>>,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(reader);
+    let mut writer = BufWriter::new(writer);
+    write_header(&mut writer)?;
+    check_header(&mut reader)?;
+    check_token(&mut reader, &mut writer, token)?;
+
+    let mut sources = HashMap::new();
+    while let Some(request) = read_request(&mut reader)? {
+        let mut response = vec![];
+        match execute(request, &mut sources, &mut open, &mut response) {
+            Ok(()) => {
+                write_u64(&mut writer, OK)?;
+                write_bytes(&mut writer, &response)?;
+            }
+            Err(error) => write_error(&mut writer, &error)?,
+        }
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+pub(crate) trait Stream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Stream for T {}
+
+/// Connects to `host:port` or to the Unix socket at `unix:<path>`.
+pub(crate) fn connect_stream(address: &str) -> io::Result<Box<dyn Stream>> {
+    match address.strip_prefix("unix:") {
+        Some(path) => Ok(Box::new(UnixStream::connect(path)?)),
+        None => {
+            let stream = TcpStream::connect(address)?;
+            stream.set_nodelay(true)?;
+            Ok(Box::new(stream))
+        }
+    }
+}
+
+/// A connection to a `memscan-agent`, requests of several threads are sent
+/// one after another.
+pub struct RemoteConnection {
+    stream: Mutex<Box<dyn Stream>>,
+}
+
+impl RemoteConnection {
+    /// Connects to an agent at `host:port` or at `unix:<path>` that was
+    /// started with `token`.
+    pub fn connect(address: &str, token: &str) -> io::Result<Self> {
+        Self::new(connect_stream(address)?, token)
+    }
+
+    pub fn new(mut stream: impl Read + Write + Send + 'static, token: &str) -> io::Result<Self> {
+        write_header(&mut stream)?;
+        check_header(&mut stream)?;
+        write_bytes(&mut stream, token.as_bytes())?;
+        stream.flush()?;
+        read_response(&mut stream)?;
+        Ok(Self {
+            stream: Mutex::new(Box::new(stream)),
+        })
+    }
+
+    /// Sends `request` and returns the body of the response.
+    fn request(&self, request: &[u8]) -> io::Result<Vec<u8>> {
+        let mut stream = self.stream.lock().unwrap();
+        stream.write_all(request)?;
+        stream.flush()?;
+
+        read_response(&mut *stream)
+    }
+
+    pub fn processes(&self) -> io::Result<Vec<ProcessInfo>> {
+        let mut request = vec![];
+        write_u64(&mut request, LIST_PROCESSES)?;
+        let body = self.request(&request)?;
+
+        let mut body = body.as_slice();
+        let mut processes = vec![];
+        for _ in 0..read_u64(&mut body)? {
+            processes.push(ProcessInfo {
+                pid: read_pid(&mut body)?,
+                uid: read_u64(&mut body)? as u32,
+                cmd: read_string(&mut body)?,
+            });
+        }
+        Ok(processes)
+    }
+
+    pub fn regions(&self, pid: i32) -> io::Result<Vec<MemoryRegion>> {
+        let mut request = vec![];
+        write_u64(&mut request, REGIONS)?;
+        write_u64(&mut request, pid as u64)?;
+        let body = self.request(&request)?;
+
+        let mut body = body.as_slice();
+        let mut regions = vec![];
+        for _ in 0..read_u64(&mut body)? {
+            regions.push(read_region(&mut body)?);
+        }
+        Ok(regions)
+    }
+
+    pub fn read(&self, pid: i32, address: u64, buffer: &mut [u8]) -> io::Result<usize> {
+        let mut read = 0;
+        for part in buffer.chunks_mut(MAX_MESSAGE as usize) {
+            let mut request = vec![];
+            write_u64(&mut request, READ)?;
+            write_u64(&mut request, pid as u64)?;
+            write_u64(&mut request, address + read as u64)?;
+            write_u64(&mut request, part.len() as u64)?;
+            let body = match self.request(&request) {
+                Ok(body) => body,
+                Err(error) if read == 0 => return Err(error),
+                Err(_) => break,
+            };
+            let length = body.len().min(part.len());
+            part[..length].copy_from_slice(&body[..length]);
+            read += length;
+            if length < part.len() {
+                break;
+            }
+        }
+        Ok(read)
+    }
+
+    pub fn write(&self, pid: i32, address: u64, data: &[u8]) -> io::Result<usize> {
+        let mut written = 0;
+        for part in data.chunks(MAX_MESSAGE as usize) {
+            let mut request = vec![];
+            write_u64(&mut request, WRITE)?;
+            write_u64(&mut request, pid as u64)?;
+            write_u64(&mut request, address + written as u64)?;
+            write_bytes(&mut request, part)?;
+            let body = self.request(&request)?;
+            let length = read_u64(&mut body.as_slice())? as usize;
+            written += length;
+            if length < part.len() {
+                break;
+            }
+        }
+        Ok(written)
+    }
+
+    /// Searches on the agent, only the matching addresses are sent back.
+    pub fn find_bytes(
+        &self,
+        pid: i32,
+        start: u64,
+        end: u64,
+        needle: &[u8],
+        alignment: usize,
+    ) -> io::Result<AddressSet> {
+        let mut request = vec![];
+        write_u64(&mut request, FIND_BYTES)?;
+        write_u64(&mut request, pid as u64)?;
+        write_u64(&mut request, start)?;
+        write_u64(&mut request, end)?;
+        write_bytes(&mut request, needle)?;
+        write_u64(&mut request, alignment as u64)?;
+        let body = self.request(&request)?;
+
+        let mut body = body.as_slice();
+        let mut found = AddressSet::new();
+        let mut address = 0u64;
+        for _ in 0..read_u64(&mut body)? {
+            address = address
+                .checked_add(read_u64(&mut body)?)
+                .ok_or_else(|| invalid_data("address out of range"))?;
+            found.push(address);
+        }
+        Ok(found)
+    }
+}
+
+/// A process behind an agent.
+pub struct RemoteSource {
+    connection: Arc<RemoteConnection>,
+    pid: i32,
+}
+
+impl RemoteSource {
+    pub fn new(connection: Arc<RemoteConnection>, pid: i32) -> Self {
+        Self { connection, pid }
+    }
+}
+
+impl MemorySource for RemoteSource {
+    fn regions(&self) -> io::Result<Vec<MemoryRegion>> {
+        self.connection.regions(self.pid)
+    }
+
+    fn read(&self, address: u64, buffer: &mut [u8]) -> io::Result<usize> {
+        self.connection.read(self.pid, address, buffer)
+    }
+
+    fn write(&mut self, address: u64, data: &[u8]) -> io::Result<usize> {
+        self.connection.write(self.pid, address, data)
+    }
+
+    fn is_remote(&self) -> bool {
+        true
+    }
+
+    fn find_bytes(
+        &self,
+        start: u64,
+        end: u64,
+        needle: &[u8],
+        alignment: usize,
+    ) -> io::Result<AddressSet> {
+        self.connection
+            .find_bytes(self.pid, start, end, needle, alignment)
+    }
+}
+
+#[test]
+fn test_remote() {
+    use crate::memory_source::BufferSource;
+
+    let (client, server) = UnixStream::pair().unwrap();
+    let agent = std::thread::spawn(move || {
+        let reader = server.try_clone().unwrap();
+        serve(reader, server, "secret", |pid| {
+            if pid != 42 {
+                return Err(io::Error::from_raw_os_error(3));
+            }
+            let mut heap = vec![0u8; 4096];
+            for offset in [8, 16, 25, 4092] {
+                heap[offset..offset + 4].copy_from_slice(&1234u32.to_le_bytes());
+            }
+            let mut source = BufferSource::new();
+            source.map(0x10000, heap);
+            Ok(Box::new(source))
+        })
+    });
+
+    let connection = Arc::new(RemoteConnection::new(client, "secret").unwrap());
+    let own_pid = std::process::id() as i32;
+    assert!(
+        connection
+            .processes()
+            .unwrap()
+            .iter()
+            .any(|process| process.pid == own_pid)
+    );
+    assert_eq!(connection.regions(7).unwrap_err().raw_os_error(), Some(3));
+
+    let mut source = RemoteSource::new(connection.clone(), 42);
+    assert!(source.is_remote());
+    assert_eq!(source.regions().unwrap()[0].end, 0x11000);
+    let found = source.find_bytes(0x10000, 0x11000, &1234u32.to_le_bytes(), 4);
+    assert_eq!(
+        found.unwrap().iter().collect::<Vec<_>>(),
+        [0x10008, 0x10010, 0x10ffc]
+    );
+    let found = source.find_bytes(0x10000, 0x11000, &1234u32.to_le_bytes(), 1);
+    assert_eq!(found.unwrap().len(), 4);
+
+    assert_eq!(source.write(0x10ffe, &[1, 2, 3]).unwrap(), 2);
+    let mut buffer = [0u8; 8];
+    assert_eq!(source.read(0x10ffc, &mut buffer).unwrap(), 4);
+    assert_eq!(buffer[..4], [0xd2, 0x04, 1, 2]);
+    let error = source.read(0x20000, &mut buffer).unwrap_err();
+    assert!(crate::memory_source::is_unreadable(&error));
+
+    drop(source);
+    drop(connection);
+    agent.join().unwrap().unwrap();
+
+    let (client, server) = UnixStream::pair().unwrap();
+    let agent = std::thread::spawn(move || {
+        let reader = server.try_clone().unwrap();
+        serve(reader, server, "secret", |_| {
+            Err(io::Error::from_raw_os_error(3))
+        })
+    });
+    assert!(RemoteConnection::new(client, "guess").is_err());
+    let error = agent.join().unwrap().unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+}
@@ -117,6 +117,63 @@ impl_find!(
     _mm256_u64_inclusive_range,
     _mm256_u64_exclusive_range
 );
+/*
+ * Note:
+ *  The integer compares are signed, so the signed types share the kernels
+ *  of their unsigned counterparts.
+ */
+impl_find!(
+    "avx2",
+    Needle,
+    i8,
+    __m256i,
+    1,
+    _mm256_set1_epi8,
+    _mm256_load_si256,
+    _mm256_movemask_epi8,
+    _mm256_cmpeq_epi8,
+    _mm256_u8_inclusive_range,
+    _mm256_u8_exclusive_range
+);
+impl_find!(
+    "avx2",
+    Needle,
+    i16,
+    __m256i,
+    2,
+    _mm256_set1_epi16,
+    _mm256_load_si256,
+    _mm256_movemask_epi8,
+    _mm256_cmpeq_epi16,
+    _mm256_u16_inclusive_range,
+    _mm256_u16_exclusive_range
+);
+impl_find!(
+    "avx2",
+    Needle,
+    i32,
+    __m256i,
+    4,
+    _mm256_set1_epi32,
+    _mm256_load_si256,
+    _mm256_movemask_epi8,
+    _mm256_cmpeq_epi32,
+    _mm256_u32_inclusive_range,
+    _mm256_u32_exclusive_range
+);
+impl_find!(
+    "avx2",
+    Needle,
+    i64,
+    __m256i,
+    8,
+    _mm256_set1_epi64x,
+    _mm256_load_si256,
+    _mm256_movemask_epi8,
+    _mm256_cmpeq_epi64,
+    _mm256_u64_inclusive_range,
+    _mm256_u64_exclusive_range
+);
 #[inline]
 unsafe fn _mm256_f32_eq(val: __m256, eq: __m256) -> __m256 {
     _mm256_cmp_ps(val, eq, _CMP_EQ_OQ)
@@ -179,5 +236,74 @@ impl_find!(
     _mm256_f64_inclusive_range,
     _mm256_f64_exclusive_range
 );
+/**
+ * Note:
+ *  There is no 128 bit compare, so both halves are compared as 64 bit lanes
+ *  and a lane is only kept if its neighbouring half matched as well.
+ *  Ordering compares the high halves and falls back to the low halves when
+ *  those are equal. The low halves, and the high halves of unsigned values,
+ *  are compared unsigned by flipping their sign bits first.
+ */
+#[inline]
+unsafe fn _mm256_set1_epi128(val: u128) -> __m256i {
+    _mm256_broadcastsi128_si256(_mm_set_epi64x((val >> 64) as i64, val as i64))
+}
+#[inline]
+unsafe fn _mm256_u128_eq(val: __m256i, eq: __m256i) -> __m256i {
+    let halves = _mm256_cmpeq_epi64(val, eq);
+    _mm256_and_si256(halves, _mm256_shuffle_epi32(halves, 0b01_00_11_10))
+}
+#[inline]
+unsafe fn _mm256_128_gt(a: __m256i, b: __m256i, high_bias: i64) -> __m256i {
+    let bias = _mm256_set_epi64x(high_bias, i64::MIN, high_bias, i64::MIN);
+    let greater = _mm256_cmpgt_epi64(_mm256_xor_si256(a, bias), _mm256_xor_si256(b, bias));
+    let equal = _mm256_cmpeq_epi64(a, b);
+    let low_greater = _mm256_shuffle_epi32(greater, 0b01_00_11_10);
+    let high = _mm256_or_si256(greater, _mm256_and_si256(equal, low_greater));
+    /* The result sits in the high half, copy it over the low half */
+    _mm256_shuffle_epi32(high, 0b11_10_11_10)
+}
+macro_rules! wide_range {
+    ($inclusive:ident, $exclusive:ident, $high_bias:expr) => {
+        #[inline]
+        unsafe fn $inclusive(val: __m256i, gt: __m256i, lt: __m256i) -> __m256i {
+            let below = _mm256_128_gt(gt, val, $high_bias);
+            let above = _mm256_128_gt(val, lt, $high_bias);
+            _mm256_andnot_si256(_mm256_or_si256(below, above), _mm256_set1_epi8(-1))
+        }
+        #[inline]
+        unsafe fn $exclusive(val: __m256i, gt: __m256i, lt: __m256i) -> __m256i {
+            _mm256_and_si256(_mm256_128_gt(val, gt, $high_bias), _mm256_128_gt(lt, val, $high_bias))
+        }
+    };
+}
+wide_range!(_mm256_u128_inclusive_range, _mm256_u128_exclusive_range, i64::MIN);
+wide_range!(_mm256_i128_inclusive_range, _mm256_i128_exclusive_range, 0);
+impl_find!(
+    "avx2",
+    Needle,
+    u128,
+    __m256i,
+    16,
+    _mm256_set1_epi128,
+    _mm256_load_si256,
+    _mm256_movemask_epi8,
+    _mm256_u128_eq,
+    _mm256_u128_inclusive_range,
+    _mm256_u128_exclusive_range
+);
+impl_find!(
+    "avx2",
+    Needle,
+    i128,
+    __m256i,
+    16,
+    _mm256_set1_epi128,
+    _mm256_load_si256,
+    _mm256_movemask_epi8,
+    _mm256_u128_eq,
+    _mm256_i128_inclusive_range,
+    _mm256_i128_exclusive_range
+);
 
 impl_tests!(Needle);
@@ -1,10 +1,42 @@
+/// Converts a needle to the lane type its splat intrinsic takes, the integer
+/// intrinsics only take signed lanes.
+pub trait Lane<T> {
+    fn lane(self) -> T;
+}
+
+macro_rules! impl_lane {
+    ($($ty:ty => $lane:ty, $convert:expr;)*) => {
+        $(impl Lane<$lane> for $ty {
+            #[inline]
+            fn lane(self) -> $lane {
+                $convert(self)
+            }
+        })*
+    };
+}
+
+impl_lane!(
+    u8 => i8, u8::cast_signed;
+    u16 => i16, u16::cast_signed;
+    u32 => i32, u32::cast_signed;
+    u64 => i64, u64::cast_signed;
+    u128 => u128, core::convert::identity;
+    i8 => i8, core::convert::identity;
+    i16 => i16, core::convert::identity;
+    i32 => i32, core::convert::identity;
+    i64 => i64, core::convert::identity;
+    i128 => u128, i128::cast_unsigned;
+    f32 => f32, core::convert::identity;
+    f64 => f64, core::convert::identity;
+);
+
 #[macro_export]
 macro_rules! unroll {
-    (6, $code:stmt) => {$code unroll!(5, $code)};
-    (5, $code:stmt) => {$code unroll!(4, $code)};
-    (4, $code:stmt) => {$code unroll!(3, $code)};
-    (3, $code:stmt) => {$code unroll!(2, $code)};
-    (2, $code:stmt) => {$code unroll!(1, $code)};
+    (6, $code:stmt) => {$code $crate::unroll!(5, $code)};
+    (5, $code:stmt) => {$code $crate::unroll!(4, $code)};
+    (4, $code:stmt) => {$code $crate::unroll!(3, $code)};
+    (3, $code:stmt) => {$code $crate::unroll!(2, $code)};
+    (2, $code:stmt) => {$code $crate::unroll!(1, $code)};
     (1, $code:stmt) => {$code}
 }
 #[macro_export]
@@ -12,8 +44,6 @@ macro_rules! impl_range {
     ($feat:expr, $name:ident, $cmpvec:ident, $cmpg:tt, $cmpl:tt, $ty:ty, $intr:ty, $stride:expr, $splat:ident, $load:ident, $movemask:ident) => {
         #[target_feature(enable = $feat)]
         unsafe fn $name(lb: $ty, ub: $ty, haystack: &[u8]) -> Option<usize> {
-            use crate::unroll;
-
             let width = core::mem::size_of::<$ty>();
             let align = width - 1;
             let reg_width = core::mem::size_of::<$intr>();
@@ -23,8 +53,8 @@ macro_rules! impl_range {
             let end = start.add(haystack.len());
 
             /* Splat mask onto wide register */
-            let mask_lb = $splat(core::mem::transmute(lb));
-            let mask_ub = $splat(core::mem::transmute(ub));
+            let mask_lb = $splat($crate::search::generic_simd::Lane::lane(lb));
+            let mask_ub = $splat($crate::search::generic_simd::Lane::lane(ub));
 
             /* Align pointer to needle size */
             let mut ptr = (start as usize + align & !align) as *const u8;
@@ -46,7 +76,7 @@ macro_rules! impl_range {
             ptr = (ptr as usize + reg_align & !reg_align) as *const u8;
 
             while ptr.add(reg_width * 6) <= end {
-                unroll!(6, {
+                $crate::unroll!(6, {
                     let result = $movemask($cmpvec($load(ptr as _), mask_lb, mask_ub));
                     if result != 0 {
                         let index = result.trailing_zeros() / $stride;
@@ -86,8 +116,6 @@ macro_rules! impl_find {
         impl $trait for $ty {
             #[target_feature(enable = $feat)]
             unsafe fn find_next(needle: $ty, haystack: &[u8]) -> Option<usize> {
-                use crate::unroll;
-
                 let width = core::mem::size_of::<$ty>();
                 let align = width - 1;
                 let reg_width = core::mem::size_of::<$intr>();
@@ -97,7 +125,7 @@ macro_rules! impl_find {
                 let end = start.add(haystack.len());
 
                 /* Splat mask onto wide register */
-                let mask = $splat(core::mem::transmute(needle));
+                let mask = $splat($crate::search::generic_simd::Lane::lane(needle));
 
                 /* Align pointer to needle size */
                 let mut ptr = (start as usize + align & !align) as *const u8;
@@ -115,7 +143,7 @@ macro_rules! impl_find {
                 ptr = (ptr as usize + reg_align & !reg_align) as *const u8;
 
                 while ptr.add(reg_width * 6) <= end {
-                    unroll!(6, {
+                    $crate::unroll!(6, {
                         let result = $movemask($cmpeq($load(ptr as _), mask));
                         if result != 0 {
                             let index = result.trailing_zeros() / $stride;
@@ -2,7 +2,9 @@
 pub mod avx2;
 #[macro_use]
 mod generic_simd;
+pub mod packed;
 pub mod primitive;
+pub mod relative;
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 pub mod sse42;
 pub mod tests;
@@ -145,8 +147,24 @@ export!(find_first_u8, find_inclusive_u8, find_exclusive_u8, u8);
 export!(find_first_u16, find_inclusive_u16, find_exclusive_u16, u16);
 export!(find_first_u32, find_inclusive_u32, find_exclusive_u32, u32);
 export!(find_first_u64, find_inclusive_u64, find_exclusive_u64, u64);
+export!(find_first_i8, find_inclusive_i8, find_exclusive_i8, i8);
+export!(find_first_i16, find_inclusive_i16, find_exclusive_i16, i16);
+export!(find_first_i32, find_inclusive_i32, find_exclusive_i32, i32);
+export!(find_first_i64, find_inclusive_i64, find_exclusive_i64, i64);
 export!(find_first_f32, find_inclusive_f32, find_exclusive_f32, f32);
 export!(find_first_f64, find_inclusive_f64, find_exclusive_f64, f64);
+export!(
+    find_first_u128,
+    find_inclusive_u128,
+    find_exclusive_u128,
+    u128
+);
+export!(
+    find_first_i128,
+    find_inclusive_i128,
+    find_exclusive_i128,
+    i128
+);
 
 #[test]
 fn test_exported() {
@@ -210,6 +228,26 @@ fn test_exported() {
         assert_eq!(find_first_f64(50.0f64, haystack), Some(50 * 8));
         assert_eq!(find_first_f64(99.0f64, haystack), Some(99 * 8));
         assert_eq!(find_first_f64(f64::NAN, haystack), None);
+
+        let mut haystack = [0u128; 100];
+        for i in 0..100 {
+            haystack[i] = i as _;
+        }
+        let haystack =
+            core::slice::from_raw_parts(haystack.as_ptr() as *const u8, haystack.len() * 16);
+        assert_eq!(find_first_u128(0u128, haystack), Some(0 * 16));
+        assert_eq!(find_first_u128(50u128, haystack), Some(50 * 16));
+        assert_eq!(find_first_u128(99u128, haystack), Some(99 * 16));
+
+        let mut haystack = [0i128; 100];
+        for i in 0..100 {
+            haystack[i] = -(i as i128);
+        }
+        let haystack =
+            core::slice::from_raw_parts(haystack.as_ptr() as *const u8, haystack.len() * 16);
+        assert_eq!(find_first_i128(0i128, haystack), Some(0 * 16));
+        assert_eq!(find_first_i128(-50i128, haystack), Some(50 * 16));
+        assert_eq!(find_first_i128(-99i128, haystack), Some(99 * 16));
     }
 }
 
@@ -264,6 +302,12 @@ fn test_iter() {
     test_iter!(u16);
     test_iter!(u32);
     test_iter!(u64);
+    test_iter!(i8);
+    test_iter!(i16);
+    test_iter!(i32);
+    test_iter!(i64);
     test_iter!(f32);
     test_iter!(f64);
+    test_iter!(u128);
+    test_iter!(i128);
 }
@@ -0,0 +1,78 @@
+use super::find_first_u8;
+
+/// Searches byte patterns of any width that are not aligned to their size,
+/// e.g. 24 bit integers packed into audio or colour buffers.
+///
+/// Candidates are located with the vectorized `u8` search for the first byte
+/// and verified afterwards.
+pub struct PackedSearch<'a> {
+    needle: Vec<u8>,
+    alignment: usize,
+    haystack: &'a [u8],
+    current: usize,
+}
+
+impl<'a> PackedSearch<'a> {
+    pub fn new(needle: &[u8], haystack: &'a [u8]) -> Self {
+        Self {
+            needle: needle.to_vec(),
+            alignment: 1,
+            haystack,
+            current: 0,
+        }
+    }
+
+    /// Only report matches at addresses that are a multiple of `alignment`.
+    pub fn alignment(mut self, alignment: usize) -> Self {
+        self.alignment = alignment.max(1);
+        self
+    }
+}
+
+impl Iterator for PackedSearch<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&first, rest) = self.needle.split_first()?;
+        let start = self.haystack.as_ptr() as usize;
+
+        while self.current + self.needle.len() <= self.haystack.len() {
+            let haystack = &self.haystack[self.current..self.haystack.len() - rest.len()];
+            let result = self.current + find_first_u8(first, haystack)?;
+            self.current = result + 1;
+
+            if (start + result).is_multiple_of(self.alignment)
+                && self.haystack[result + 1..].starts_with(rest)
+            {
+                return Some(result);
+            }
+        }
+
+        None
+    }
+}
+
+#[test]
+fn test_packed() {
+    let mut haystack = [0u8; 100];
+    haystack[7..10].copy_from_slice(&[0x12, 0x34, 0x56]);
+    haystack[30..33].copy_from_slice(&[0x12, 0x34, 0x56]);
+    haystack[50..52].copy_from_slice(&[0x12, 0x34]);
+    haystack[97..100].copy_from_slice(&[0x12, 0x34, 0x56]);
+
+    let mut iter = PackedSearch::new(&[0x12, 0x34, 0x56], &haystack);
+    assert_eq!(iter.next(), Some(7));
+    assert_eq!(iter.next(), Some(30));
+    assert_eq!(iter.next(), Some(97));
+    assert_eq!(iter.next(), None);
+
+    let offset = haystack.as_ptr() as usize;
+    let expected: Vec<usize> = [7, 30, 97]
+        .into_iter()
+        .filter(|index| (offset + index).is_multiple_of(3))
+        .collect();
+    let found: Vec<usize> = PackedSearch::new(&[0x12, 0x34, 0x56], &haystack)
+        .alignment(3)
+        .collect();
+    assert_eq!(found, expected);
+}
@@ -0,0 +1,225 @@
+/// How a value is stored relative to the value shown to the user, with an
+/// unknown constant `k`.
+#[derive(Debug, PartialEq, Eq, Default, Clone, Copy)]
+pub enum Relation {
+    /// Stored as `value + k`
+    #[default]
+    Offset,
+    /// Stored as `value * k`
+    Scale,
+    /// Stored as `value ^ k`
+    Xor,
+}
+
+pub trait Relative: Copy + PartialEq {
+    /// Checks whether two stored values can encode two observed values
+    /// under the same constant. Integer arithmetic wraps like the target does.
+    fn relates(relation: Relation, observed: (Self, Self), stored: (Self, Self)) -> bool;
+
+    /// Reverses the byte order.
+    fn swap_bytes(self) -> Self;
+}
+
+macro_rules! impl_relative {
+    ($($ty:ty),+) => {
+        $(
+            impl Relative for $ty {
+                #[inline]
+                fn relates(relation: Relation, observed: (Self, Self), stored: (Self, Self)) -> bool {
+                    match relation {
+                        Relation::Offset => {
+                            stored.1.wrapping_sub(stored.0) == observed.1.wrapping_sub(observed.0)
+                        }
+                        Relation::Xor => stored.1 ^ stored.0 == observed.1 ^ observed.0,
+                        /* Cross multiply, a changing value may not be stored as a constant */
+                        Relation::Scale => {
+                            stored.0.wrapping_mul(observed.1) == stored.1.wrapping_mul(observed.0)
+                                && (observed.0 == observed.1 || stored.0 != stored.1)
+                        }
+                    }
+                }
+
+                #[inline]
+                fn swap_bytes(self) -> Self {
+                    <$ty>::swap_bytes(self)
+                }
+            }
+        )+
+    };
+}
+
+impl_relative!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+/// Checks a series of stored values against the observed series, e.g. values
+/// read from the same address over time.
+pub fn relates_all<T: Relative>(relation: Relation, observed: &[T], stored: &[T]) -> bool {
+    observed.len() == stored.len()
+        && observed
+            .windows(2)
+            .zip(stored.windows(2))
+            .all(|(observed, stored)| {
+                T::relates(relation, (observed[0], observed[1]), (stored[0], stored[1]))
+            })
+}
+
+/// Checks whether `current` changed from `last` the same way `last` changed
+/// from `previous`.
+pub fn changed_by_same_delta<T: Relative>(
+    relation: Relation,
+    previous: T,
+    last: T,
+    current: T,
+) -> bool {
+    T::relates(relation, (previous, last), (last, current))
+}
+
+/// Finds sequences of adjacent values whose deltas match the observed values,
+/// independent of the constant they are encoded with.
+pub struct RelativeSearch<'a, T: Relative> {
+    relation: Relation,
+    values: Vec<T>,
+    stride: usize,
+    swap: bool,
+    haystack: &'a [u8],
+    current: usize,
+}
+
+impl<'a, T: Relative> RelativeSearch<'a, T> {
+    pub fn new(relation: Relation, values: Vec<T>, haystack: &'a [u8]) -> Self {
+        Self {
+            relation,
+            values,
+            stride: core::mem::size_of::<T>(),
+            swap: false,
+            haystack,
+            current: 0,
+        }
+    }
+
+    /// Distance in bytes between adjacent values, defaults to the value size.
+    pub fn stride(mut self, stride: usize) -> Self {
+        self.stride = stride.max(core::mem::size_of::<T>());
+        self
+    }
+
+    /// Reverse the bytes of the stored values, for memory of the other byte order.
+    pub fn swap_bytes(mut self, swap: bool) -> Self {
+        self.swap = swap;
+        self
+    }
+
+    unsafe fn read(&self, offset: usize) -> T {
+        let value = core::ptr::read_unaligned(self.haystack.as_ptr().add(offset) as *const T);
+        if self.swap { value.swap_bytes() } else { value }
+    }
+
+    unsafe fn matches_at(&self, offset: usize) -> bool {
+        self.values.windows(2).enumerate().all(|(index, observed)| {
+            let a = self.read(offset + index * self.stride);
+            let b = self.read(offset + (index + 1) * self.stride);
+            T::relates(self.relation, (observed[0], observed[1]), (a, b))
+        })
+    }
+}
+
+impl<T: Relative> Iterator for RelativeSearch<'_, T> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let width = core::mem::size_of::<T>();
+        let align = width - 1;
+        let span = (self.values.len().max(1) - 1) * self.stride + width;
+
+        /* Align offset to needle size */
+        let start = self.haystack.as_ptr() as usize;
+        let mut offset = ((start + self.current + align) & !align) - start;
+
+        while offset + span <= self.haystack.len() {
+            if unsafe { self.matches_at(offset) } {
+                self.current = offset + width;
+                return Some(offset);
+            }
+            offset += width;
+        }
+
+        self.current = self.haystack.len();
+        None
+    }
+}
+
+#[test]
+fn test_relates() {
+    assert!(u32::relates(Relation::Offset, (10, 15), (110, 115)));
+    assert!(!u32::relates(Relation::Offset, (10, 15), (110, 116)));
+    assert!(u8::relates(Relation::Offset, (250, 5), (4, 15)));
+    assert!(u32::relates(
+        Relation::Xor,
+        (10, 15),
+        (10 ^ 0x55, 15 ^ 0x55)
+    ));
+    assert!(!u32::relates(
+        Relation::Xor,
+        (10, 15),
+        (10 ^ 0x55, 15 ^ 0x56)
+    ));
+    assert!(i64::relates(Relation::Scale, (3, 7), (300, 700)));
+    assert!(!i64::relates(Relation::Scale, (3, 7), (300, 701)));
+    assert!(!i64::relates(Relation::Scale, (3, 7), (0, 0)));
+
+    assert!(relates_all(
+        Relation::Offset,
+        &[1u16, 4, 2],
+        &[101, 104, 102]
+    ));
+    assert!(!relates_all(
+        Relation::Offset,
+        &[1u16, 4, 2],
+        &[101, 104, 103]
+    ));
+    assert!(changed_by_same_delta(Relation::Offset, 10u32, 13, 16));
+    assert!(!changed_by_same_delta(Relation::Offset, 10u32, 13, 17));
+    assert!(changed_by_same_delta(Relation::Scale, 2u32, 4, 8));
+}
+
+#[test]
+fn test_relative_search() {
+    let mut haystack: [u32; 100] = core::array::from_fn(|i| (i * i) as u32);
+    /* 5, 9, 2 encoded with k = 1000 at index 40 */
+    haystack[40] = 1005;
+    haystack[41] = 1009;
+    haystack[42] = 1002;
+    /* and with k = 0x1234 xored at index 70 */
+    haystack[70] = 5 ^ 0x1234;
+    haystack[71] = 9 ^ 0x1234;
+    haystack[72] = 2 ^ 0x1234;
+    let haystack =
+        unsafe { core::slice::from_raw_parts(haystack.as_ptr() as *const u8, haystack.len() * 4) };
+
+    let mut iter = RelativeSearch::new(Relation::Offset, vec![5u32, 9, 2], haystack);
+    assert_eq!(iter.next(), Some(40 * 4));
+    assert_eq!(iter.next(), None);
+
+    let mut iter = RelativeSearch::new(Relation::Xor, vec![5u32, 9, 2], haystack);
+    assert_eq!(iter.next(), Some(70 * 4));
+    assert_eq!(iter.next(), None);
+
+    let mut haystack = [0u64; 32];
+    haystack[3] = 30;
+    haystack[5] = 70;
+    let haystack =
+        unsafe { core::slice::from_raw_parts(haystack.as_ptr() as *const u8, haystack.len() * 8) };
+    let mut iter = RelativeSearch::new(Relation::Scale, vec![3u64, 7], haystack).stride(16);
+    assert_eq!(iter.next(), Some(3 * 8));
+    assert_eq!(iter.next(), None);
+
+    /* 5, 9 stored with k = 0x100 in the other byte order */
+    let mut haystack = [0u16; 8];
+    haystack[2] = 0x105u16.swap_bytes();
+    haystack[3] = 0x109u16.swap_bytes();
+    let haystack =
+        unsafe { core::slice::from_raw_parts(haystack.as_ptr() as *const u8, haystack.len() * 2) };
+    let iter = RelativeSearch::new(Relation::Offset, vec![5u16, 9], haystack);
+    assert!(!iter.collect::<Vec<_>>().contains(&4));
+    let mut iter = RelativeSearch::new(Relation::Offset, vec![5u16, 9], haystack).swap_bytes(true);
+    assert_eq!(iter.next(), Some(4));
+}
@@ -107,6 +107,63 @@ impl_find!(
     _mm_u64_inclusive_range,
     _mm_u64_exclusive_range
 );
+/*
+ * Note:
+ *  The integer compares are signed, so the signed types share the kernels
+ *  of their unsigned counterparts.
+ */
+impl_find!(
+    "sse4.2",
+    Needle,
+    i8,
+    __m128i,
+    1,
+    _mm_set1_epi8,
+    _mm_load_si128,
+    _mm_movemask_epi8,
+    _mm_cmpeq_epi8,
+    _mm_u8_inclusive_range,
+    _mm_u8_exclusive_range
+);
+impl_find!(
+    "sse4.2",
+    Needle,
+    i16,
+    __m128i,
+    2,
+    _mm_set1_epi16,
+    _mm_load_si128,
+    _mm_movemask_epi8,
+    _mm_cmpeq_epi16,
+    _mm_u16_inclusive_range,
+    _mm_u16_exclusive_range
+);
+impl_find!(
+    "sse4.2",
+    Needle,
+    i32,
+    __m128i,
+    4,
+    _mm_set1_epi32,
+    _mm_load_si128,
+    _mm_movemask_epi8,
+    _mm_cmpeq_epi32,
+    _mm_u32_inclusive_range,
+    _mm_u32_exclusive_range
+);
+impl_find!(
+    "sse4.2",
+    Needle,
+    i64,
+    __m128i,
+    8,
+    _mm_set1_epi64x,
+    _mm_load_si128,
+    _mm_movemask_epi8,
+    _mm_cmpeq_epi64,
+    _mm_u64_inclusive_range,
+    _mm_u64_exclusive_range
+);
 #[inline]
 unsafe fn _mm_f32_inclusive_range(val: __m128, gt: __m128, lt: __m128) -> __m128 {
     _mm_and_ps(_mm_cmpge_ps(val, gt), _mm_cmple_ps(val, lt))
@@ -149,5 +206,74 @@ impl_find!(
     _mm_f64_inclusive_range,
     _mm_f64_exclusive_range
 );
+/**
+ * Note:
+ *  There is no 128 bit compare, so both halves are compared as 64 bit lanes
+ *  and a lane is only kept if its neighbouring half matched as well.
+ *  Ordering compares the high halves and falls back to the low halves when
+ *  those are equal. The low halves, and the high halves of unsigned values,
+ *  are compared unsigned by flipping their sign bits first.
+ */
+#[inline]
+unsafe fn _mm_set1_epi128(val: u128) -> __m128i {
+    _mm_set_epi64x((val >> 64) as i64, val as i64)
+}
+#[inline]
+unsafe fn _mm_u128_eq(val: __m128i, eq: __m128i) -> __m128i {
+    let halves = _mm_cmpeq_epi64(val, eq);
+    _mm_and_si128(halves, _mm_shuffle_epi32(halves, 0b01_00_11_10))
+}
+#[inline]
+unsafe fn _mm_128_gt(a: __m128i, b: __m128i, high_bias: i64) -> __m128i {
+    let bias = _mm_set_epi64x(high_bias, i64::MIN);
+    let greater = _mm_cmpgt_epi64(_mm_xor_si128(a, bias), _mm_xor_si128(b, bias));
+    let equal = _mm_cmpeq_epi64(a, b);
+    let low_greater = _mm_shuffle_epi32(greater, 0b01_00_11_10);
+    let high = _mm_or_si128(greater, _mm_and_si128(equal, low_greater));
+    /* The result sits in the high half, copy it over the low half */
+    _mm_shuffle_epi32(high, 0b11_10_11_10)
+}
+macro_rules! wide_range {
+    ($inclusive:ident, $exclusive:ident, $high_bias:expr) => {
+        #[inline]
+        unsafe fn $inclusive(val: __m128i, gt: __m128i, lt: __m128i) -> __m128i {
+            let below = _mm_128_gt(gt, val, $high_bias);
+            let above = _mm_128_gt(val, lt, $high_bias);
+            _mm_andnot_si128(_mm_or_si128(below, above), _mm_set1_epi8(-1))
+        }
+        #[inline]
+        unsafe fn $exclusive(val: __m128i, gt: __m128i, lt: __m128i) -> __m128i {
+            _mm_and_si128(_mm_128_gt(val, gt, $high_bias), _mm_128_gt(lt, val, $high_bias))
+        }
+    };
+}
+wide_range!(_mm_u128_inclusive_range, _mm_u128_exclusive_range, i64::MIN);
+wide_range!(_mm_i128_inclusive_range, _mm_i128_exclusive_range, 0);
+impl_find!(
+    "sse4.2",
+    Needle,
+    u128,
+    __m128i,
+    16,
+    _mm_set1_epi128,
+    _mm_load_si128,
+    _mm_movemask_epi8,
+    _mm_u128_eq,
+    _mm_u128_inclusive_range,
+    _mm_u128_exclusive_range
+);
+impl_find!(
+    "sse4.2",
+    Needle,
+    i128,
+    __m128i,
+    16,
+    _mm_set1_epi128,
+    _mm_load_si128,
+    _mm_movemask_epi8,
+    _mm_u128_eq,
+    _mm_i128_inclusive_range,
+    _mm_i128_exclusive_range
+);
 
 impl_tests!(Needle);
@@ -98,6 +98,41 @@ macro_rules! impl_tests {
                     "exclusive u64"
                 );
 
+                let mut haystack = [0i32; 100];
+                for i in 0..100 {
+                    haystack[i] = i as i32 - 50;
+                }
+                let haystack =
+                    core::slice::from_raw_parts(haystack.as_ptr() as *const u8, haystack.len() * 4);
+                assert_eq!(
+                    $trait::find_next(-50i32, haystack),
+                    Some(0 * 4),
+                    "first i32"
+                );
+                assert_eq!($trait::find_next(49i32, haystack), Some(99 * 4), "last i32");
+                assert_eq!(
+                    $trait::find_inclusive_range(-10i32, 10i32, &haystack),
+                    Some(40 * 4),
+                    "inclusive i32"
+                );
+                assert_eq!(
+                    $trait::find_exclusive_range(-10i32, 10i32, &haystack),
+                    Some(41 * 4),
+                    "exclusive i32"
+                );
+
+                let mut haystack = [0i16; 100];
+                for i in 0..100 {
+                    haystack[i] = 50 - i as i16;
+                }
+                let haystack =
+                    core::slice::from_raw_parts(haystack.as_ptr() as *const u8, haystack.len() * 2);
+                assert_eq!(
+                    $trait::find_inclusive_range(-20i16, -10i16, &haystack),
+                    Some(60 * 2),
+                    "inclusive i16"
+                );
+
                 let mut haystack = [0f32; 100];
                 for i in 0..100 {
                     haystack[i] = i as _;
@@ -163,6 +198,114 @@ macro_rules! impl_tests {
                     "exclusive f64"
                 );
                 assert_eq!($trait::find_next(f64::NAN, haystack), None);
+
+                let mut haystack = [0u128; 100];
+                for i in 0..100 {
+                    haystack[i] = (i as u128) << 64 | i as u128;
+                }
+                let haystack = core::slice::from_raw_parts(
+                    haystack.as_ptr() as *const u8,
+                    haystack.len() * 16,
+                );
+                assert_eq!(
+                    $trait::find_next(0u128, haystack),
+                    Some(0 * 16),
+                    "first u128"
+                );
+                assert_eq!(
+                    $trait::find_next(50u128 << 64 | 50, haystack),
+                    Some(50 * 16),
+                    "middle u128"
+                );
+                assert_eq!(
+                    $trait::find_next(99u128 << 64 | 99, haystack),
+                    Some(99 * 16),
+                    "last u128"
+                );
+                assert_eq!(
+                    $trait::find_next(50u128 << 64 | 51, haystack),
+                    None,
+                    "half match u128"
+                );
+                assert_eq!(
+                    $trait::find_inclusive_range(10u128 << 64, 20u128 << 64, &haystack),
+                    Some(10 * 16),
+                    "inclusive u128"
+                );
+                assert_eq!(
+                    $trait::find_exclusive_range(10u128 << 64, 20u128 << 64, &haystack),
+                    Some(10 * 16),
+                    "exclusive u128"
+                );
+
+                /* Ordered by the low halves, whose sign bits are set */
+                let mut haystack = [0u128; 100];
+                for i in 0..100 {
+                    haystack[i] = u128::MAX - (99 - i as u128);
+                }
+                let haystack = core::slice::from_raw_parts(
+                    haystack.as_ptr() as *const u8,
+                    haystack.len() * 16,
+                );
+                assert_eq!(
+                    $trait::find_inclusive_range(u128::MAX - 60, u128::MAX - 40, &haystack),
+                    Some(39 * 16),
+                    "inclusive low u128"
+                );
+                assert_eq!(
+                    $trait::find_exclusive_range(u128::MAX - 60, u128::MAX - 40, &haystack),
+                    Some(40 * 16),
+                    "exclusive low u128"
+                );
+
+                let mut haystack = [0i128; 100];
+                for i in 0..100 {
+                    haystack[i] = i as i128 - 50;
+                }
+                let haystack = core::slice::from_raw_parts(
+                    haystack.as_ptr() as *const u8,
+                    haystack.len() * 16,
+                );
+                assert_eq!(
+                    $trait::find_next(-50i128, haystack),
+                    Some(0 * 16),
+                    "first i128"
+                );
+                assert_eq!(
+                    $trait::find_next(49i128, haystack),
+                    Some(99 * 16),
+                    "last i128"
+                );
+                assert_eq!(
+                    $trait::find_inclusive_range(-10i128, 10i128, &haystack),
+                    Some(40 * 16),
+                    "inclusive i128"
+                );
+                assert_eq!(
+                    $trait::find_exclusive_range(-10i128, 10i128, &haystack),
+                    Some(41 * 16),
+                    "exclusive i128"
+                );
+
+                /* Values crossing from the low into the high half */
+                let mut haystack = [0i128; 100];
+                for i in 0..100 {
+                    haystack[i] = (i as i128 - 50) << 63;
+                }
+                let haystack = core::slice::from_raw_parts(
+                    haystack.as_ptr() as *const u8,
+                    haystack.len() * 16,
+                );
+                assert_eq!(
+                    $trait::find_inclusive_range(-10i128 << 63, 10i128 << 63, &haystack),
+                    Some(40 * 16),
+                    "inclusive wide i128"
+                );
+                assert_eq!(
+                    $trait::find_exclusive_range(-10i128 << 63, 10i128 << 63, &haystack),
+                    Some(41 * 16),
+                    "exclusive wide i128"
+                );
             }
         }
     };
@@ -0,0 +1,439 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    ops::Range,
+    path::Path,
+};
+
+use crate::{
+    memory_source::{MemoryRegion, MemorySource, read_pages, read_region, write_region},
+    pagemap,
+    varint::{read_bytes, read_i64, read_u64, write_bytes, write_i64, write_u64},
+};
+
+const MAGIC: &[u8; 4] = b"MSSS";
+const VERSION: u32 = 3;
+
+const RAW: u64 = 0;
+/// Alternating literal and zero runs, see `compress`
+const ZERO_RUNS: u64 = 1;
+
+/// Bytes read from the source at once while capturing.
+const CAPTURE_CHUNK: usize = 1024 * 1024;
+
+/// The memory of a process captured at one point in time, so it can be
+/// searched offline or attached to bug reports.
+///
+/// The file starts with the magic `MSSS` and a little endian version, all
+/// other integers are varints. The signed pid, command name and a free form
+/// description of the scan settings are followed by every region with its
+/// `/proc/<pid>/maps` metadata, the holes in its content as offset and
+/// length, an FNV-1a checksum of its content and the content itself, either
+/// raw or with runs of zeros compressed.
+#[derive(Debug, Default, PartialEq)]
+pub struct Snapshot {
+    pub pid: i32,
+    pub command: String,
+    /// How the snapshot was taken, e.g. scan settings as JSON
+    pub settings: String,
+    /// Regions sorted by address
+    pub regions: Vec<SnapshotRegion>,
+}
+
+/// A region and the bytes that could be read from it.
+#[derive(Debug, PartialEq)]
+pub struct SnapshotRegion {
+    pub region: MemoryRegion,
+    /// Content from the start of the region up to the last byte that could
+    /// be read, holes are zero
+    pub bytes: Vec<u8>,
+    /// Ascending ranges of `bytes` that couldn't be read
+    pub holes: Vec<Range<usize>>,
+}
+
+impl SnapshotRegion {
+    /// The readable ranges of `bytes` within `range`.
+    pub fn captured(&self, range: Range<usize>) -> Vec<Range<usize>> {
+        let range = range.start..range.end.min(self.bytes.len());
+        let mut parts = vec![];
+        let mut start = range.start;
+        for hole in &self.holes {
+            if hole.end <= start {
+                continue;
+            }
+            if hole.start >= range.end {
+                break;
+            }
+            if hole.start > start {
+                parts.push(start..hole.start);
+            }
+            start = hole.end;
+        }
+        if start < range.end {
+            parts.push(start..range.end);
+        }
+        parts
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_string(writer: &mut impl Write, string: &str) -> io::Result<()> {
+    write_bytes(writer, string.as_bytes())
+}
+
+fn read_string(reader: &mut impl Read) -> io::Result<String> {
+    String::from_utf8(read_bytes(reader)?).map_err(|_| invalid_data("string is not UTF-8"))
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100_0000_01b3)
+    })
+}
+
+/// Encodes `bytes` as pairs of a literal run and a zero run, each preceded
+/// by its length. Short zero runs stay in the literals.
+fn compress(bytes: &[u8]) -> Vec<u8> {
+    const MIN_ZERO_RUN: usize = 16;
+
+    let mut compressed = vec![];
+    let mut literal_start = 0;
+    let mut position = 0;
+    while position < bytes.len() {
+        let zeros = bytes[position..]
+            .iter()
+            .take_while(|&&byte| byte == 0)
+            .count();
+        if zeros < MIN_ZERO_RUN && position + zeros < bytes.len() {
+            position += zeros.max(1);
+            continue;
+        }
+        write_bytes(&mut compressed, &bytes[literal_start..position]).unwrap();
+        write_u64(&mut compressed, zeros as u64).unwrap();
+        position += zeros;
+        literal_start = position;
+    }
+    if literal_start < bytes.len() {
+        write_bytes(&mut compressed, &bytes[literal_start..]).unwrap();
+        write_u64(&mut compressed, 0).unwrap();
+    }
+    compressed
+}
+
+fn decompress(mut compressed: &[u8], length: usize) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(length);
+    while !compressed.is_empty() {
+        bytes.extend(read_bytes(&mut compressed)?);
+        let zeros = read_u64(&mut compressed)? as usize;
+        if bytes.len() + zeros > length {
+            return Err(invalid_data("region content is too long"));
+        }
+        bytes.resize(bytes.len() + zeros, 0);
+    }
+    Ok(bytes)
+}
+
+impl Snapshot {
+    /// Reads `regions` from `source`. Parts of a region that can't be read
+    /// are kept as holes, other errors like the process exiting fail the
+    /// capture.
+    pub fn capture(
+        source: &dyn MemorySource,
+        pid: i32,
+        command: String,
+        settings: String,
+        mut regions: Vec<MemoryRegion>,
+    ) -> io::Result<Self> {
+        regions.sort_by_key(|region| region.start);
+        let mut chunk = vec![0u8; CAPTURE_CHUNK];
+        let regions = regions
+            .into_iter()
+            .map(|region| {
+                let mut bytes = vec![];
+                let mut holes = vec![];
+                for chunk_start in (region.start..region.end).step_by(CAPTURE_CHUNK) {
+                    let length = ((region.end - chunk_start) as usize).min(CAPTURE_CHUNK);
+                    let chunk = &mut chunk[..length];
+                    let offset = (chunk_start - region.start) as usize;
+                    for part in read_pages(source, chunk_start, chunk, pagemap::page_size())? {
+                        /* Everything since the last part read is a hole, the
+                        unreadable end of a region is left out */
+                        if offset + part.start > bytes.len() {
+                            holes.push(bytes.len()..offset + part.start);
+                            bytes.resize(offset + part.start, 0);
+                        }
+                        bytes.extend_from_slice(&chunk[part]);
+                    }
+                }
+                Ok(SnapshotRegion {
+                    region,
+                    bytes,
+                    holes,
+                })
+            })
+            .collect::<io::Result<_>>()?;
+
+        Ok(Self {
+            pid,
+            command,
+            settings,
+            regions,
+        })
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write, compressed: bool) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        write_i64(writer, self.pid.into())?;
+        write_string(writer, &self.command)?;
+        write_string(writer, &self.settings)?;
+
+        write_u64(writer, self.regions.len() as u64)?;
+        for SnapshotRegion {
+            region,
+            bytes,
+            holes,
+        } in &self.regions
+        {
+            write_region(writer, region)?;
+            write_u64(writer, holes.len() as u64)?;
+            for hole in holes {
+                write_u64(writer, hole.start as u64)?;
+                write_u64(writer, hole.len() as u64)?;
+            }
+            write_u64(writer, bytes.len() as u64)?;
+            write_u64(writer, fnv1a(bytes))?;
+            if compressed {
+                write_u64(writer, ZERO_RUNS)?;
+                write_bytes(writer, &compress(bytes))?;
+            } else {
+                write_u64(writer, RAW)?;
+                write_bytes(writer, bytes)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid_data("not a snapshot file"));
+        }
+        let version = u32::from_le_bytes(header[4..].try_into().unwrap());
+        if version != VERSION {
+            return Err(invalid_data(&format!(
+                "unsupported snapshot version {}",
+                version
+            )));
+        }
+
+        let pid = i32::try_from(read_i64(reader)?).map_err(|_| invalid_data("pid out of range"))?;
+        let command = read_string(reader)?;
+        let settings = read_string(reader)?;
+
+        let mut regions = vec![];
+        for _ in 0..read_u64(reader)? {
+            let region = read_region(reader)?;
+            let mut holes: Vec<Range<usize>> = vec![];
+            for _ in 0..read_u64(reader)? {
+                let start = read_u64(reader)?;
+                let end = start
+                    .checked_add(read_u64(reader)?)
+                    .ok_or_else(|| invalid_data("hole out of range"))?;
+                if end > region.end - region.start
+                    || holes.last().is_some_and(|last| last.end as u64 > start)
+                {
+                    return Err(invalid_data("holes are out of order"));
+                }
+                holes.push(start as usize..end as usize);
+            }
+            let length = read_u64(reader)?;
+            if length > region.end - region.start
+                || holes.last().is_some_and(|last| last.end as u64 > length)
+            {
+                return Err(invalid_data("region content is too long"));
+            }
+            let checksum = read_u64(reader)?;
+            let bytes = match read_u64(reader)? {
+                RAW => read_bytes(reader)?,
+                ZERO_RUNS => decompress(&read_bytes(reader)?, length as usize)?,
+                _ => return Err(invalid_data("unknown region encoding")),
+            };
+            if bytes.len() as u64 != length || fnv1a(&bytes) != checksum {
+                return Err(invalid_data(&format!(
+                    "checksum mismatch in region {:#x}",
+                    region.start
+                )));
+            }
+            regions.push(SnapshotRegion {
+                region,
+                bytes,
+                holes,
+            });
+        }
+
+        Ok(Self {
+            pid,
+            command,
+            settings,
+            regions,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>, compressed: bool) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer, compressed)?;
+        writer.flush()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read_from(&mut BufReader::new(File::open(path)?))
+    }
+}
+
+/// Snapshots are read only, like the dumps they replace.
+impl MemorySource for Snapshot {
+    fn regions(&self) -> io::Result<Vec<MemoryRegion>> {
+        Ok(self
+            .regions
+            .iter()
+            .map(|region| region.region.clone())
+            .collect())
+    }
+
+    fn read(&self, address: u64, buffer: &mut [u8]) -> io::Result<usize> {
+        let index = self
+            .regions
+            .partition_point(|region| region.region.start <= address)
+            .checked_sub(1)
+            .filter(|&index| self.regions[index].region.contains(address))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("address {:#x} is not in the snapshot", address),
+                )
+            })?;
+        let region = &self.regions[index];
+        let offset = (address - region.region.start) as usize;
+        /* Reads stop at holes and fail inside them, like at unreadable pages */
+        let hole = region.holes.partition_point(|hole| hole.end <= offset);
+        if region
+            .holes
+            .get(hole)
+            .is_some_and(|hole| hole.start <= offset)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("address {:#x} was not captured", address),
+            ));
+        }
+        let available = region
+            .captured(offset..offset + buffer.len())
+            .first()
+            .map_or(&[][..], |part| &region.bytes[part.clone()]);
+        buffer[..available.len()].copy_from_slice(available);
+        Ok(available.len())
+    }
+}
+
+#[test]
+fn test_snapshot() {
+    use crate::memory_source::{BufferSource, ExitedSource};
+
+    let mut heap = vec![0u8; 4096];
+    heap[100..104].copy_from_slice(&[1, 2, 3, 4]);
+    heap[4000] = 0xff;
+    let mut source = BufferSource::new();
+    source.map(0x5000, heap.clone());
+    source.map(0x1000, vec![7; 10]);
+    let mut regions = source.regions().unwrap();
+    regions[1].pathname = Some("[heap]".to_string());
+    /* Only partially readable */
+    regions[0].end = 0x2000;
+
+    /* Pseudo processes like dumps and remote targets have negative pids */
+    let snapshot = Snapshot::capture(
+        &source,
+        -3,
+        "game".to_string(),
+        "{\"scope\":\"All\"}".to_string(),
+        regions.clone(),
+    )
+    .unwrap();
+    assert_eq!(snapshot.regions[0].bytes, vec![7; 10]);
+    assert_eq!(snapshot.regions[1].bytes, heap);
+
+    for compressed in [false, true] {
+        let mut file = vec![];
+        snapshot.write_to(&mut file, compressed).unwrap();
+        assert_eq!(compressed, file.len() < 1000);
+        let loaded = Snapshot::read_from(&mut file.as_slice()).unwrap();
+        assert_eq!(loaded, snapshot);
+
+        /* Flip a content byte near the end */
+        let last = file.len() - if compressed { 2 } else { 1 };
+        file[last] ^= 1;
+        assert!(Snapshot::read_from(&mut file.as_slice()).is_err());
+    }
+
+    let mut buffer = [0u8; 8];
+    assert_eq!(snapshot.read(0x5000 + 100, &mut buffer[..4]).unwrap(), 4);
+    assert_eq!(buffer[..4], [1, 2, 3, 4]);
+    assert_eq!(snapshot.read(0x1008, &mut buffer).unwrap(), 2);
+    assert_eq!(snapshot.read(0x1100, &mut buffer).unwrap(), 0);
+    assert!(snapshot.read(0x4000, &mut buffer).is_err());
+
+    /* Unmapped regions are empty, a source that fails otherwise fails the capture */
+    let mut unmapped = regions[0].clone();
+    (unmapped.start, unmapped.end) = (0x9000, 0xa000);
+    let partial =
+        Snapshot::capture(&source, 1, String::new(), String::new(), vec![unmapped]).unwrap();
+    assert!(partial.regions[0].bytes.is_empty());
+
+    /* Memory after an unreadable page is kept, the page becomes a hole */
+    let page = pagemap::page_size() as usize;
+    let mut source = BufferSource::new();
+    source.map(0x100000, vec![1; page]);
+    source.map(0x100000 + 2 * page as u64, vec![2; page]);
+    let mut region = regions[0].clone();
+    (region.start, region.end) = (0x100000, 0x100000 + 4 * page as u64);
+    let snapshot =
+        Snapshot::capture(&source, 1, String::new(), String::new(), vec![region]).unwrap();
+    let captured = &snapshot.regions[0];
+    assert_eq!(captured.bytes.len(), 3 * page);
+    assert_eq!(captured.holes, vec![page..2 * page]);
+    assert_eq!(
+        captured.captured(0..4 * page),
+        vec![0..page, 2 * page..3 * page]
+    );
+    assert_eq!(captured.bytes[2 * page], 2);
+
+    let mut file = vec![];
+    snapshot.write_to(&mut file, true).unwrap();
+    assert_eq!(Snapshot::read_from(&mut file.as_slice()).unwrap(), snapshot);
+    assert_eq!(snapshot.read(0x100000, &mut buffer).unwrap(), 8);
+    assert_eq!(
+        snapshot
+            .read(0x100000 + page as u64 - 2, &mut buffer)
+            .unwrap(),
+        2
+    );
+    assert!(snapshot.read(0x100000 + page as u64, &mut buffer).is_err());
+    assert_eq!(
+        snapshot
+            .read(0x100000 + 2 * page as u64, &mut buffer)
+            .unwrap(),
+        8
+    );
+    assert_eq!(
+        snapshot
+            .read(0x100000 + 3 * page as u64, &mut buffer)
+            .unwrap(),
+        0
+    );
+    assert!(Snapshot::capture(&ExitedSource, 1, String::new(), String::new(), regions).is_err());
+}
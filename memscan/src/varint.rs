@@ -0,0 +1,89 @@
+use std::io::{self, Read, Write};
+
+pub fn write_u64(writer: &mut impl Write, mut value: u64) -> io::Result<()> {
+    let mut buffer = [0u8; 10];
+    let mut length = 0;
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            buffer[length] = byte;
+            length += 1;
+            break;
+        }
+        buffer[length] = byte | 0x80;
+        length += 1;
+    }
+    writer.write_all(&buffer[..length])
+}
+
+pub fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0u8];
+        reader.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7F) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "varint too long",
+    ))
+}
+
+/// Signed values are zigzag encoded so small negative numbers stay short.
+pub fn write_i64(writer: &mut impl Write, value: i64) -> io::Result<()> {
+    write_u64(writer, ((value << 1) ^ (value >> 63)) as u64)
+}
+
+pub fn read_i64(reader: &mut impl Read) -> io::Result<i64> {
+    let value = read_u64(reader)?;
+    Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+}
+
+pub fn write_bytes(writer: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    write_u64(writer, bytes.len() as u64)?;
+    writer.write_all(bytes)
+}
+
+/// The length comes from the file, so the buffer only grows as far as the
+/// bytes actually read instead of being allocated up front.
+pub fn read_bytes(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let length = read_u64(reader)?;
+    let mut bytes = vec![];
+    reader.take(length).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != length {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(bytes)
+}
+
+#[test]
+fn test_varint() {
+    let mut buffer = vec![];
+    for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+        write_u64(&mut buffer, value).unwrap();
+    }
+    for value in [0, -1, 1, -64, 64, i64::MIN, i64::MAX] {
+        write_i64(&mut buffer, value).unwrap();
+    }
+    write_bytes(&mut buffer, b"libgame.so").unwrap();
+    assert_eq!(buffer[..4], [0, 1, 127, 0x80]);
+
+    let mut reader = buffer.as_slice();
+    for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+        assert_eq!(read_u64(&mut reader).unwrap(), value);
+    }
+    for value in [0, -1, 1, -64, 64, i64::MIN, i64::MAX] {
+        assert_eq!(read_i64(&mut reader).unwrap(), value);
+    }
+    assert_eq!(read_bytes(&mut reader).unwrap(), b"libgame.so");
+    assert!(read_u64(&mut reader).is_err());
+
+    let mut truncated = vec![];
+    write_u64(&mut truncated, u64::MAX).unwrap();
+    truncated.extend_from_slice(b"abc");
+    assert!(read_bytes(&mut truncated.as_slice()).is_err());
+}